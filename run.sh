@@ -11,7 +11,7 @@ mkdir "$UPG_DIR"/demo
 mkdir "$UPG_DIR"/asterinas
 
 export RUST_LOG=debug
-export UPG_RUST_STD_LIBRARY=$(rustc --print=sysroot)/lib/rustlib/src/rust/library
+STD_LIBRARY=$(rustc --print=sysroot)/lib/rustlib/src/rust/library
 
 export RAPX_STD_JSON=$PWD/assets/std.json
 export RAPX_STD_MAPPING_DIR=$PWD/assets/fn_name_mapping
@@ -21,16 +21,15 @@ export RAPX_STD_OUT=$PWD/data/tags/std.json
 gen_std() {
   pushd tests/demo
   cargo clean
-  # unset UPG_RUST_STD_LIBRARY
-  # Generate $UPG_DIR/$crate
-  UPG_DIR=$UPG_DIR/demo upg
+  # Generate $UPG_DIR/demo/$crate
+  cargo upg --out-dir "$UPG_DIR/demo" --std "$STD_LIBRARY"
   popd
 }
 
 gen_fn_name_mapping() {
   pushd tests/demo
   cargo clean
-  UPG_DRIVER=upg-rapx-adpator upg
+  cargo upg --out-dir "$UPG_DIR" --std "$STD_LIBRARY" --driver upg-rapx-adpator
   cp "$UPG_DIR"/_rapx/core.json assets/fn_name_mapping/
   cp "$UPG_DIR"/_rapx/std.json assets/fn_name_mapping/
   cp "$UPG_DIR"/_rapx/alloc.json assets/fn_name_mapping/
@@ -48,8 +47,8 @@ gen_data() {
 
   pushd ./tag-asterinas/ostd/
   cargo clean
-  unset UPG_RUST_STD_LIBRARY # disable build-std
-  UPG_DIR=$UPG_DIR/asterinas CARGO_BUILD_ARGS="--target x86_64-unknown-none" upg
+  # std is not built
+  cargo upg --out-dir "$UPG_DIR/asterinas" --target x86_64-unknown-none
   popd
 
   # Prune logs.
@@ -1,8 +1,12 @@
-use crate::utils::{FxHashMap, FxIndexSet, ThinVec};
+use crate::{
+    diagnostics::{self, DiagnosticKind},
+    utils::{FxHashMap, FxIndexSet, ThinVec},
+};
 use derive_more::Debug;
 use rustc_public::{
+    CrateDef,
     mir::ProjectionElem,
-    ty::{AdtDef, AdtKind},
+    ty::{AdtDef, AdtKind, GenericArgs},
 };
 use std::sync::Arc;
 
@@ -10,7 +14,9 @@ use std::sync::Arc;
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Adt {
     pub def: AdtDef,
-    // pub args: GenericArgs,
+    /// Generic arguments the adt is instantiated with, e.g. `[u8]` for `Vec<u8>`.
+    /// Empty args means the generic adt itself, see [`Adt::generic`].
+    pub args: GenericArgs,
     pub variant_fields: Arc<Vec<VaraintField>>,
 }
 
@@ -18,10 +24,25 @@ impl Adt {
     pub fn new(def: AdtDef) -> Adt {
         Adt {
             def,
+            args: GenericArgs(Vec::new()),
             variant_fields: Arc::new(new_variant_fields(def)),
         }
     }
 
+    /// The same adt instantiated with the args. Variant fields are shared.
+    pub fn with_args(&self, args: GenericArgs) -> Adt {
+        Adt {
+            def: self.def,
+            args,
+            variant_fields: self.variant_fields.clone(),
+        }
+    }
+
+    /// The adt without generic arguments, so all instances map to the same one.
+    pub fn generic(&self) -> Adt {
+        self.with_args(GenericArgs(Vec::new()))
+    }
+
     /// Returns Some iff the adt is struct or union.
     pub fn num_fields(&self) -> Option<usize> {
         for vfield in &*self.variant_fields {
@@ -57,10 +78,13 @@ fn new_variant_fields(def: AdtDef) -> Vec<VaraintField> {
             }
             AdtKind::Struct | AdtKind::Union => {
                 if variant_idx != 0 {
-                    panic!(
-                        "{def:?} is a struct with multiple variants: {:#?}",
-                        def.variants_iter().collect::<Vec<_>>()
+                    let message = format!(
+                        "{:?} has {} variants, and only the first one is kept",
+                        def.kind(),
+                        def.num_variants()
                     );
+                    diagnostics::report(DiagnosticKind::MultipleVariants, def.name(), message);
+                    break;
                 }
                 if fields.is_empty() {
                     variant_fields.push(VaraintField {
@@ -197,10 +221,14 @@ pub struct VaraintField {
     pub name: Box<str>,
 }
 
+/// Generic adts keyed by their definitions.
 pub type CacheAdt = FxHashMap<AdtDef, Adt>;
 
-/// Retrieve Adt via def. The function is faster because it skips
-/// construction of VaraintField if found.
-pub fn new_adt(def: AdtDef, cache: &mut CacheAdt) -> Adt {
-    cache.entry(def).or_insert_with(|| Adt::new(def)).clone()
+/// Retrieve Adt via def and instantiate it with args. The function is faster because
+/// it skips construction of VaraintField if found.
+pub fn new_adt(def: AdtDef, args: &GenericArgs, cache: &mut CacheAdt) -> Adt {
+    cache
+        .entry(def)
+        .or_insert_with(|| Adt::new(def))
+        .with_args(args.clone())
 }
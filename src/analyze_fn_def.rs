@@ -1,11 +1,16 @@
-use crate::utils::ThinVec;
+use crate::{
+    info_adt::FieldAccessKind,
+    utils::{FxHashMap, ThinVec},
+};
 use rustc_public::{
-    CrateDef,
+    CrateDef, CrateItem,
     mir::{
-        Body, Mutability, Place,
-        visit::{Location, MirVisitor},
+        Body, Local, Mutability, Operand, Place, ProjectionElem, Rvalue, Statement, StatementKind,
+        Terminator, TerminatorKind,
+        alloc::GlobalAlloc,
+        visit::{Location, MirVisitor, PlaceContext},
     },
-    ty::{RigidTy, Span, Ty, TyKind},
+    ty::{ConstantKind, MirConst, RigidTy, Span, Ty, TyKind},
 };
 use std::fmt::{self, Debug};
 
@@ -19,6 +24,14 @@ pub fn collect(body: &Body) -> Collector {
 pub struct Collector {
     pub v_ty: ThinVec<Type>,
     pub v_place: ThinVec<Place2>,
+    /// Statics and consts accessed in the body.
+    pub v_static: ThinVec<StaticAccess>,
+    /// Places dropped by `Drop` terminators.
+    pub v_drop: ThinVec<Place2>,
+    /// Places being written.
+    pub v_write: ThinVec<Place2>,
+    /// Locals assigned by the address of a static, and whether the address is mutable.
+    static_locals: FxHashMap<Local, (CrateItem, bool)>,
 }
 
 impl MirVisitor for Collector {
@@ -32,18 +45,95 @@ impl MirVisitor for Collector {
         self.super_ty(ty);
     }
 
-    fn visit_place(
-        &mut self,
-        place: &Place,
-        ptx: rustc_public::mir::visit::PlaceContext,
-        location: Location,
-    ) {
+    fn visit_statement(&mut self, stmt: &Statement, location: Location) {
+        // A static is accessed through its address like `_1 = {alloc1: *mut usize}`.
+        if let StatementKind::Assign(place, Rvalue::Use(Operand::Constant(constant))) = &stmt.kind
+            && place.projection.is_empty()
+            && let Some(item) = static_item(&constant.const_)
+        {
+            let mutable = matches!(
+                constant.const_.ty().kind(),
+                TyKind::RigidTy(
+                    RigidTy::RawPtr(_, Mutability::Mut) | RigidTy::Ref(_, _, Mutability::Mut)
+                )
+            );
+            self.static_locals.insert(place.local, (item, mutable));
+        }
+        self.super_statement(stmt, location);
+    }
+
+    fn visit_terminator(&mut self, term: &Terminator, location: Location) {
+        if let TerminatorKind::Drop { place, .. } = &term.kind {
+            self.v_drop.push(Place2 {
+                place: place.clone(),
+                span: location.span(),
+            });
+        }
+        self.super_terminator(term, location);
+    }
+
+    fn visit_place(&mut self, place: &Place, ptx: PlaceContext, location: Location) {
+        if let Some(&(item, mutable)) = self.static_locals.get(&place.local) {
+            let kind = match place.projection.first() {
+                Some(ProjectionElem::Deref) if ptx.is_mutating() => Some(FieldAccessKind::Write),
+                Some(ProjectionElem::Deref) => Some(FieldAccessKind::Read),
+                // The local is being assigned by the address.
+                None if ptx.is_mutating() => None,
+                // The address escapes, e.g. passed to a function.
+                _ if mutable => Some(FieldAccessKind::Other),
+                _ => Some(FieldAccessKind::Read),
+            };
+            if let Some(kind) = kind {
+                self.v_static.push(StaticAccess { item, kind });
+            }
+        }
+
+        if ptx.is_mutating() {
+            self.v_write.push(Place2 {
+                place: place.clone(),
+                span: location.span(),
+            });
+        }
         self.v_place.push(Place2 {
             place: place.clone(),
             span: location.span(),
         });
         self.super_place(place, ptx, location);
     }
+
+    fn visit_mir_const(&mut self, constant: &MirConst, location: Location) {
+        // A const item is used as a value. Promoted consts are part of the body itself.
+        // Generic assoc consts like `SizedTypeProperties::ALIGN` mostly come from
+        // compiler inserted checks, so they are not counted.
+        if let ConstantKind::Unevaluated(uneval) = constant.kind()
+            && uneval.promoted.is_none()
+            && uneval.args.0.is_empty()
+        {
+            self.v_static.push(StaticAccess {
+                item: CrateItem(uneval.def.def_id()),
+                kind: FieldAccessKind::Read,
+            });
+        }
+        self.super_mir_const(constant, location);
+    }
+}
+
+/// Returns the static if the constant is the address of it.
+pub fn static_item(constant: &MirConst) -> Option<CrateItem> {
+    if let ConstantKind::Allocated(alloc) = constant.kind()
+        && let [(_, prov)] = &*alloc.provenance.ptrs
+        && let GlobalAlloc::Static(def) = GlobalAlloc::from(prov.0)
+    {
+        Some(def.into())
+    } else {
+        None
+    }
+}
+
+/// Access to a static or const item in the body.
+pub struct StaticAccess {
+    pub item: CrateItem,
+    pub kind: FieldAccessKind,
 }
 
 pub struct Type {
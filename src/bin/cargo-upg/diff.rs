@@ -0,0 +1,270 @@
+//! Compare two outputs of a crate, e.g. `$UPG_DIR/<crate>` of two releases.
+
+use crate::{Result, merge::write_json, surface::Surface};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::PathBuf,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// The old output directory of a crate.
+    old: PathBuf,
+    /// The new output directory of a crate.
+    new: PathBuf,
+    /// Also write the report in JSON to the file.
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+    /// Output format of the JSON file.
+    #[arg(long, value_enum, default_value_t)]
+    format: JsonFormat,
+}
+
+/// Formats of the JSON report. Unlike `--format` of the build, SARIF is not supported.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum JsonFormat {
+    #[default]
+    Pretty,
+    Compact,
+}
+
+pub fn run(args: DiffArgs) -> Result<()> {
+    let mut old = Surface::default();
+    let mut new = Surface::default();
+    for (surface, dir) in [(&mut old, &args.old), (&mut new, &args.new)] {
+        ensure!(dir.is_dir(), "{dir:?} is not a directory.");
+        surface.read_crate(dir)?;
+    }
+
+    let report = Report::new(&old, &new);
+    print!("{report}");
+    if let Some(path) = &args.json {
+        let compact = matches!(args.format, JsonFormat::Compact);
+        write_json(path, &serde_json::to_value(&report)?, compact)?;
+    }
+    Ok(())
+}
+
+/// Items added and removed in the new output.
+#[derive(Debug, Serialize)]
+pub struct Change<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+}
+
+impl<T: Ord + Clone> Change<T> {
+    fn new(old: &BTreeSet<T>, new: &BTreeSet<T>) -> Self {
+        Change {
+            added: new.difference(old).cloned().collect(),
+            removed: old.difference(new).cloned().collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Call {
+    pub caller: String,
+    pub callee: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldWriter {
+    pub adt: String,
+    pub field: String,
+    pub writer: String,
+}
+
+/// `Unsafe` kinds in `navi/unsafe_fns.json`. None means the function is neither unsafe nor
+/// calls unsafe functions.
+#[derive(Debug, Serialize)]
+pub struct KindChange {
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Functions declared as unsafe.
+    pub unsafe_fns: Change<String>,
+    /// Calls to unsafe functions.
+    pub unsafe_calls: Change<Call>,
+    /// Function to changed tags. Only functions in both outputs are compared.
+    pub tags: BTreeMap<String, Change<String>>,
+    /// Functions writing adt fields which are not written by them in the old output.
+    pub new_field_writers: Vec<FieldWriter>,
+    /// Function to changed `Unsafe` kinds. Only functions in both outputs are compared.
+    pub unsafe_kinds: BTreeMap<String, KindChange>,
+}
+
+impl Report {
+    pub fn new(old: &Surface, new: &Surface) -> Self {
+        let calls = |surface: &Surface| -> BTreeSet<Call> {
+            let calls = surface.unsafe_callees.iter();
+            calls
+                .map(|(caller, callee)| Call {
+                    caller: caller.clone(),
+                    callee: callee.clone(),
+                })
+                .collect()
+        };
+        // Function to tags, built once instead of scanning all tags for each function.
+        let tags = |surface: &Surface| -> BTreeMap<String, BTreeSet<String>> {
+            let mut map = BTreeMap::<_, BTreeSet<_>>::new();
+            for (name, tag) in &surface.tags {
+                map.entry(name.clone()).or_default().insert(tag.clone());
+            }
+            map
+        };
+        let (old_tags, new_tags) = (tags(old), tags(new));
+        let no_tags = BTreeSet::new();
+
+        let mut report = Report {
+            unsafe_fns: Change::new(&old.unsafe_fns, &new.unsafe_fns),
+            unsafe_calls: Change::new(&calls(old), &calls(new)),
+            tags: BTreeMap::new(),
+            new_field_writers: new
+                .field_writers
+                .difference(&old.field_writers)
+                .map(|(adt, field, writer)| FieldWriter {
+                    adt: adt.clone(),
+                    field: field.clone(),
+                    writer: writer.clone(),
+                })
+                .collect(),
+            unsafe_kinds: BTreeMap::new(),
+        };
+
+        for name in old.functions.intersection(&new.functions) {
+            let old_tags = old_tags.get(name).unwrap_or(&no_tags);
+            let new_tags = new_tags.get(name).unwrap_or(&no_tags);
+            let change = Change::new(old_tags, new_tags);
+            if !change.is_empty() {
+                report.tags.insert(name.clone(), change);
+            }
+
+            let old_kind = old.unsafe_kinds.get(name);
+            let new_kind = new.unsafe_kinds.get(name);
+            if old_kind != new_kind {
+                let change = KindChange {
+                    old: old_kind.cloned(),
+                    new: new_kind.cloned(),
+                };
+                report.unsafe_kinds.insert(name.clone(), change);
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Unsafe functions:")?;
+        for name in &self.unsafe_fns.added {
+            writeln!(f, "  + {name}")?;
+        }
+        for name in &self.unsafe_fns.removed {
+            writeln!(f, "  - {name}")?;
+        }
+
+        writeln!(f, "Unsafe calls:")?;
+        for Call { caller, callee } in &self.unsafe_calls.added {
+            writeln!(f, "  + {caller} -> {callee}")?;
+        }
+        for Call { caller, callee } in &self.unsafe_calls.removed {
+            writeln!(f, "  - {caller} -> {callee}")?;
+        }
+
+        writeln!(f, "Tags:")?;
+        for (name, change) in &self.tags {
+            let added = change.added.iter().map(|tag| format!(" +{tag}"));
+            let removed = change.removed.iter().map(|tag| format!(" -{tag}"));
+            writeln!(f, "  {name}:{}", added.chain(removed).collect::<String>())?;
+        }
+
+        writeln!(f, "New field writers:")?;
+        for FieldWriter { adt, field, writer } in &self.new_field_writers {
+            writeln!(f, "  + {adt}.{field} <- {writer}")?;
+        }
+
+        writeln!(f, "Unsafe kinds:")?;
+        for (name, KindChange { old, new }) in &self.unsafe_kinds {
+            let old = old.as_deref().unwrap_or("Safe");
+            let new = new.as_deref().unwrap_or("Safe");
+            writeln!(f, "  {name}: {old} -> {new}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set<T: Ord>(items: impl IntoIterator<Item = T>) -> BTreeSet<T> {
+        items.into_iter().collect()
+    }
+
+    fn pair(a: &str, b: &str) -> (String, String) {
+        (a.to_owned(), b.to_owned())
+    }
+
+    #[test]
+    fn report() {
+        let old = Surface {
+            functions: set(["f", "g", "h"].map(String::from)),
+            unsafe_fns: set(["f".to_owned()]),
+            unsafe_callees: set([pair("g", "f")]),
+            tags: set([pair("f", "ValidPtr"), pair("f", "Align"), pair("h", "Init")]),
+            unsafe_kinds: [pair("f", "Unsafe"), pair("g", "Caller")].into(),
+            field_writers: set([("S".to_owned(), "x".to_owned(), "g".to_owned())]),
+        };
+        let new = Surface {
+            functions: set(["f", "g", "k"].map(String::from)),
+            unsafe_fns: set(["k".to_owned()]),
+            unsafe_callees: set([pair("g", "f"), pair("f", "k")]),
+            tags: set([
+                pair("f", "ValidPtr"),
+                pair("f", "NonNull"),
+                pair("k", "Init"),
+            ]),
+            unsafe_kinds: [pair("g", "Caller"), pair("k", "Unsafe")].into(),
+            field_writers: set([
+                ("S".to_owned(), "x".to_owned(), "g".to_owned()),
+                ("S".to_owned(), "y".to_owned(), "f".to_owned()),
+            ]),
+        };
+        let report = Report::new(&old, &new);
+
+        assert_eq!(report.unsafe_fns.added, ["k"]);
+        assert_eq!(report.unsafe_fns.removed, ["f"]);
+        let call = Call {
+            caller: "f".to_owned(),
+            callee: "k".to_owned(),
+        };
+        assert_eq!(report.unsafe_calls.added, [call]);
+        assert!(report.unsafe_calls.removed.is_empty());
+
+        // h and k are not in both outputs, and g has no change.
+        assert_eq!(report.tags.keys().collect::<Vec<_>>(), ["f"]);
+        assert_eq!(report.tags["f"].added, ["NonNull"]);
+        assert_eq!(report.tags["f"].removed, ["Align"]);
+
+        assert_eq!(report.new_field_writers.len(), 1);
+        let FieldWriter { adt, field, writer } = &report.new_field_writers[0];
+        assert_eq!((&**adt, &**field, &**writer), ("S", "y", "f"));
+
+        assert_eq!(report.unsafe_kinds.keys().collect::<Vec<_>>(), ["f"]);
+        let KindChange { old, new } = &report.unsafe_kinds["f"];
+        assert_eq!((old.as_deref(), new.as_deref()), (Some("Unsafe"), None));
+
+        let text = report.to_string();
+        assert!(text.contains("  f: +NonNull -Align\n"), "{text}");
+        assert!(text.contains("  f: Unsafe -> Safe\n"), "{text}");
+    }
+}
@@ -0,0 +1,220 @@
+//! Env vars passed from `cargo upg` to itself as the rustc wrapper invoked by cargo.
+
+use crate::{Format, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::{
+    env::var,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+
+#[allow(non_snake_case)]
+pub struct EnvVar {
+    /// The rustc driver to analyze target crates.
+    pub UPG_DRIVER: String,
+    /// The output directory, which is also read by the driver.
+    pub UPG_DIR: PathBuf,
+}
+
+impl EnvVar {
+    pub fn write_rustflags_json(&self, json: &serde_json::Value) -> Result<()> {
+        const JSON_FILE: &str = "rustflags.json";
+
+        let path = self.UPG_DIR.join(JSON_FILE);
+        let writer = std::fs::File::create(&path)?;
+        serde_json::to_writer_pretty(writer, json)?;
+        let path = path.canonicalize()?;
+        info!("{path:?} is written.");
+        Ok(())
+    }
+}
+
+const UPG_DRIVER: &str = "UPG_DRIVER";
+const UPG_DIR: &str = "UPG_DIR";
+
+/// Only available in the rustc wrapper.
+pub static ENV: LazyLock<EnvVar> = LazyLock::new(|| {
+    let dir = PathBuf::from(var(UPG_DIR).expect("`UPG_DIR` is set by cargo upg"));
+    init_log(&dir);
+    EnvVar {
+        UPG_DRIVER: var(UPG_DRIVER).expect("`UPG_DRIVER` is set by cargo upg"),
+        UPG_DIR: dir,
+    }
+});
+
+/// Env vars for the rustc wrapper and the driver.
+pub fn set_env_var(driver: &str, dir: &Path) -> [(&'static str, String); 2] {
+    [
+        (UPG_DRIVER, driver.to_owned()),
+        (UPG_DIR, dir.display().to_string()),
+    ]
+}
+
+/// Write logs to `upg.log` in the output directory. Filtered by `RUST_LOG`.
+pub fn init_log(dir: &Path) {
+    let log_file = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(dir.join("upg.log"))
+        .unwrap();
+
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_writer(log_file))
+        .with(EnvFilter::from_default_env())
+        .init();
+}
+
+/// The sysroot of the toolchain.
+pub fn sysroot() -> Result<PathBuf> {
+    let output = std::process::Command::new("rustc")
+        .arg("--print=sysroot")
+        .output()?
+        .stdout;
+    let sysroot = std::str::from_utf8(&output)?.trim();
+    Ok(PathBuf::from(sysroot).canonicalize()?)
+}
+
+/// The library in rust-src component, which is the source of std.
+pub fn std_library_path() -> Result<PathBuf> {
+    let mut path = sysroot()?;
+    path.extend(["lib", "rustlib", "src", "rust", "library"]);
+    Ok(path.canonicalize()?)
+}
+
+const WRAPPER: &str = "WRAPPER";
+/// Inner env var to know if the process is cargo wrapper (verify_rust_std).
+pub fn is_wrapper() -> bool {
+    var(WRAPPER).as_deref() == Ok("1")
+}
+/// Set inner env var when cargo wrapper is to run.
+pub fn set_wrapper() -> (&'static str, String) {
+    (WRAPPER, "1".to_owned())
+}
+
+pub fn set_rustc_wrapper() -> Result<(&'static str, String)> {
+    let exe = std::env::current_exe()?;
+    Ok(("RUSTC", exe.display().to_string()))
+}
+
+pub fn set_upg_continue() -> (&'static str, String) {
+    ("UPG_CONTINUE", "1".to_owned())
+}
+
+/// Output format of JSON files, read by the driver.
+pub fn set_upg_format(format: Format) -> (&'static str, String) {
+    let format = match format {
+        Format::Pretty => "pretty",
+        Format::Compact => "compact",
+        Format::Sarif => "sarif",
+    };
+    ("UPG_FORMAT", format.to_owned())
+}
+
+/// Emit findings as warnings in the driver.
+pub fn set_upg_lint() -> (&'static str, String) {
+    ("UPG_LINT", "1".to_owned())
+}
+
+/// Env vars for the check mode of the driver. Paths are absolute because rustc runs in
+/// different directories.
+pub fn set_check(
+    baseline: Option<&Path>,
+    bless: bool,
+    allowlist: Option<&Path>,
+) -> Result<Vec<(&'static str, String)>> {
+    let mut vars = Vec::new();
+    let Some(baseline) = baseline else {
+        return Ok(vars);
+    };
+    std::fs::create_dir_all(baseline)?;
+    vars.push((
+        "UPG_BASELINE",
+        baseline.canonicalize()?.display().to_string(),
+    ));
+    if bless {
+        vars.push(("UPG_BLESS", "1".to_owned()));
+    }
+    if let Some(allowlist) = allowlist {
+        vars.push((
+            "UPG_ALLOWLIST",
+            allowlist.canonicalize()?.display().to_string(),
+        ));
+    }
+    Ok(vars)
+}
+
+const UPG_ARGS: &[&str] = &[
+    "-Cpanic=abort",
+    "-Csymbol-mangling-version=v0",
+    "-Zunstable-options",
+    "-Ztrim-diagnostic-paths=no",
+    "-Zhuman_readable_cgu_names",
+    "-Zalways-encode-mir",
+    "-Zcrate-attr=feature(register_tool)",
+    "-Zcrate-attr=register_tool(rapx)",
+    "-Zmir-enable-passes=-RemoveStorageMarkers",
+];
+
+/// RUSTFLAGS for all crates, encoded for `CARGO_ENCODED_RUSTFLAGS`.
+pub fn set_rustflags(remap: &Remap, extra: Vec<String>) -> (&'static str, String) {
+    let flags: Vec<_> = UPG_ARGS
+        .iter()
+        .map(|arg| arg.to_string())
+        .chain(remap.rustc_flags())
+        .chain(extra)
+        .collect();
+    ("CARGO_ENCODED_RUSTFLAGS", flags.join("\u{1f}"))
+}
+
+/// Path prefixes in spans are replaced by placeholders to be portable across machines.
+/// The mapping is written to `remap.json` to recover the real paths.
+#[derive(Debug, Serialize)]
+pub struct Remap {
+    /// The placeholder to real path prefixes.
+    #[serde(flatten)]
+    map: IndexMap<&'static str, Vec<PathBuf>>,
+}
+
+impl Remap {
+    pub fn new(workspace_root: PathBuf) -> Result<Self> {
+        let mut map = IndexMap::new();
+        // In order of priority from low to high, because rustc prefers the last matched one.
+        if let Some(cargo_home) = cargo_home() {
+            // Registry sources and git checkouts, not the whole CARGO_HOME which may contain
+            // other things like installed binaries or the workspace itself.
+            let registry = cargo_home.join("registry").join("src");
+            let git = cargo_home.join("git").join("checkouts");
+            map.insert("$CARGO", vec![registry, git]);
+        }
+        map.insert("$WORKSPACE", vec![workspace_root]);
+        // Std sources are under `lib/rustlib/src/rust/library` of the sysroot.
+        map.insert("$SYSROOT", vec![sysroot()?]);
+        Ok(Remap { map })
+    }
+
+    fn rustc_flags(&self) -> impl Iterator<Item = String> {
+        self.map.iter().flat_map(|(to, v_from)| {
+            v_from
+                .iter()
+                .map(move |from| format!("--remap-path-prefix={}={to}", from.display()))
+        })
+    }
+
+    pub fn write_json(&self, dir: &Path) -> Result<()> {
+        let path = dir.join("remap.json");
+        let writer = std::fs::File::create(&path)?;
+        serde_json::to_writer_pretty(writer, self)?;
+        info!("{path:?} is written.");
+        Ok(())
+    }
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    let dir = match var("CARGO_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(var("HOME").ok()?).join(".cargo"),
+    };
+    dir.canonicalize().ok()
+}
@@ -0,0 +1,306 @@
+//! This is a cargo subcommand `cargo upg` to the rustc driver `unsafety-propagation-graph`.
+//!
+//! It runs `cargo build` with itself as the rustc wrapper, which runs the driver on target
+//! crates and plain rustc on other crates.
+
+use clap::{Parser, ValueEnum};
+use eyre::{Context, Result};
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+#[macro_use]
+extern crate tracing;
+
+#[macro_use]
+extern crate eyre;
+
+mod diff;
+mod env;
+use env::ENV;
+
+mod matrix;
+mod merge;
+mod surface;
+
+mod target;
+use target::{Selection, Targets, UpgToml};
+
+#[derive(Parser, Debug)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum CargoCli {
+    Upg(Cli),
+}
+
+/// Run `cargo build` with the driver analyzing selected crates.
+///
+/// Crates can also be selected in the `[targets]` table of `upg.toml` in the current directory.
+/// If no crate is selected, core, std, alloc, demo, and ostd are analyzed.
+#[derive(clap::Args, Debug)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+    /// The directory to write JSONs into. Defaults to `upg` under cargo's target directory.
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+    /// Build and analyze std from source. Defaults to the library in rust-src component.
+    #[arg(long, value_name = "LIBRARY")]
+    std: Option<Option<PathBuf>>,
+    /// Build for the target triple.
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+    /// Package to build.
+    #[arg(short, long, value_name = "SPEC")]
+    package: Vec<String>,
+    /// Space or comma separated list of features to activate.
+    #[arg(short = 'F', long)]
+    features: Vec<String>,
+    /// The rustc driver to analyze target crates.
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "unsafety-propagation-graph"
+    )]
+    driver: String,
+    /// Output format of JSON files, or `sarif` to only write a SARIF report per crate.
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+    /// Analyze each configuration in the `[[matrix]]` array of `upg.toml`, and write the
+    /// difference of them to `matrix.json`.
+    #[arg(long)]
+    matrix: bool,
+    /// Check crates against baselines `<DIR>/<crate>.json`, and fail on new unsafe calls,
+    /// unsafe blocks, or raw field writes without safety tags or safety comments.
+    #[arg(long, value_name = "DIR")]
+    baseline: Option<PathBuf>,
+    /// Run `cargo check` and report findings as warnings, which can be allowed like
+    /// `#[allow(rapx::missing_safety_tag)]`.
+    #[arg(long)]
+    lint: bool,
+    /// Write current findings to the baselines instead of checking.
+    #[arg(long, requires = "baseline")]
+    bless: bool,
+    /// A file of functions or findings like `caller -> callee` to skip in the check.
+    #[arg(long, value_name = "FILE", requires = "baseline")]
+    allowlist: Option<PathBuf>,
+    #[command(flatten)]
+    targets: Selection,
+    /// Extra arguments passed to `cargo build`.
+    #[arg(last = true)]
+    cargo_args: Vec<String>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+    /// Compare two outputs of a crate, and report changes of the unsafety surface.
+    Diff(diff::DiffArgs),
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum Format {
+    #[default]
+    Pretty,
+    Compact,
+    /// `upg.sarif` for code-scanning tools instead of JSONs.
+    Sarif,
+}
+
+fn main() -> Result<()> {
+    // arguments passed to rustc
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.as_slice() == ["-vv"] {
+        // cargo invokes `rustc -vV` first
+        run("rustc", &["-vV".to_owned()], &[])
+    } else if env::is_wrapper() {
+        // then cargo invokes `rustc - --crate-name ___ --print=file-names`
+        // if args[0] == "-" {
+        // `rustc -` is a substitute file name from stdin
+        // see https://rust-lang.zulipchat.com/#narrow/channel/182449-t-compiler.2Fhelp/topic/.E2.9C.94.20What.20does.20.60rustc.20-.60do.3F/with/514494493
+        //     args[0] = "src/main.rs".to_owned();
+        // }
+
+        let targets = Targets::from_env()?;
+        if let Some(crate_name) = target::crate_name(&args)
+            && targets.is_target_crate(crate_name)
+        {
+            let json = serde_json::json!({
+                "rustc": format!("rustc {}", args.join(" ")),
+                "crate_name": crate_name,
+                "targets": targets.patterns,
+            });
+            ENV.write_rustflags_json(&json)?;
+            build_core(args)
+        } else {
+            // build non-core crates
+            run("rustc", &args, &[])
+        }
+    } else {
+        let CargoCli::Upg(mut cli) = CargoCli::parse();
+        if let Some(Subcommand::Diff(args)) = cli.command {
+            return diff::run(args);
+        }
+        let upg_toml = UpgToml::read()?;
+        cli.targets.merge(upg_toml.targets);
+        run_cargo(cli, upg_toml.matrix)
+    }
+}
+
+fn run_cargo(mut cli: Cli, configs: Vec<matrix::Config>) -> Result<()> {
+    let metadata = target::cargo_metadata(&["--no-deps"])?;
+    let target_dir = target::metadata_path(&metadata, "target_directory")?;
+    let out_dir = match &cli.out_dir {
+        Some(dir) => dir.clone(),
+        None => target_dir.join("upg"),
+    };
+    std::fs::create_dir_all(&out_dir).with_context(|| format!("Failed to create {out_dir:?}"))?;
+    let out_dir = out_dir.canonicalize()?;
+    env::init_log(&out_dir);
+
+    let targets = std::mem::take(&mut cli.targets).resolve()?;
+    info!(?targets);
+
+    let workspace_root = target::metadata_path(&metadata, "workspace_root")?;
+    let remap = env::Remap::new(workspace_root.clone())?;
+    remap.write_json(&out_dir)?;
+
+    let std_library = match &cli.std {
+        Some(Some(library)) => Some(library.canonicalize()?),
+        Some(None) => Some(env::std_library_path()?),
+        None => None,
+    };
+
+    let check = env::set_check(cli.baseline.as_deref(), cli.bless, cli.allowlist.as_deref())?;
+
+    let build = Build {
+        check,
+        workspace_name: workspace_root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        cli,
+        target_dir,
+        std_library,
+        remap,
+        targets,
+    };
+
+    if !build.cli.matrix {
+        return build.run(&out_dir, None);
+    }
+
+    matrix::check_configs(&configs)?;
+    for config in &configs {
+        build.run(&matrix::config_dir(&out_dir, config), Some(config))?;
+    }
+    let cmp = matrix::compare(&out_dir, &configs, &build.targets, build.compact())?;
+    println!(
+        "{} functions, unsafe callees of {} functions, and tags of {} functions \
+         are not present in all configurations. See {:?}.",
+        cmp.functions.len(),
+        cmp.unsafe_callees.len(),
+        cmp.tags.len(),
+        out_dir.join("matrix.json")
+    );
+    Ok(())
+}
+
+/// States shared by `cargo build` of all configurations.
+struct Build {
+    cli: Cli,
+    /// Env vars for the check mode.
+    check: Vec<(&'static str, String)>,
+    target_dir: PathBuf,
+    std_library: Option<PathBuf>,
+    remap: env::Remap,
+    targets: Targets,
+    workspace_name: String,
+}
+
+impl Build {
+    fn compact(&self) -> bool {
+        matches!(self.cli.format, Format::Compact)
+    }
+
+    /// Run `cargo build` writing JSONs into the directory. A configuration from the matrix
+    /// overrides the target triple and comes with extra arguments.
+    fn run(&self, out_dir: &Path, config: Option<&matrix::Config>) -> Result<()> {
+        let cli = &self.cli;
+        std::fs::create_dir_all(out_dir)
+            .with_context(|| format!("Failed to create {out_dir:?}"))?;
+
+        let cmd = if cli.lint { "check" } else { "build" };
+        let mut args = vec![cmd.to_owned()];
+        if self.std_library.is_some() {
+            args.push("-Zbuild-std=core,alloc,std".to_owned());
+        }
+        let target = config.and_then(|config| config.target.as_ref());
+        if let Some(target) = target.or(cli.target.as_ref()) {
+            args.extend(["--target".to_owned(), target.clone()]);
+        }
+        for package in &cli.package {
+            args.extend(["--package".to_owned(), package.clone()]);
+        }
+        for features in &cli.features {
+            args.extend(["--features".to_owned(), features.clone()]);
+        }
+        let mut cfg = Vec::new();
+        if let Some(config) = config {
+            // A separate target directory for each configuration, otherwise cargo skips crates
+            // compiled in another configuration, and they are not analyzed.
+            let target_dir = self.target_dir.join("upg-matrix").join(&config.name);
+            args.extend(["--target-dir".to_owned(), target_dir.display().to_string()]);
+            args.extend(config.cargo_args());
+            cfg.extend(config.rustc_flags());
+        }
+        args.extend(cli.cargo_args.iter().cloned());
+
+        let mut vars = Vec::from(env::set_env_var(&cli.driver, out_dir));
+        if let Some(library) = &self.std_library {
+            // Build std from the library.
+            vars.push(("__CARGO_TESTS_ONLY_SRC_ROOT", library.display().to_string()));
+        }
+        vars.extend([
+            env::set_rustflags(&self.remap, cfg),
+            env::set_rustc_wrapper()?,
+            env::set_wrapper(),
+            env::set_upg_continue(),
+            env::set_upg_format(self.cli.format),
+            self.targets.set_env()?,
+        ]);
+        vars.extend(self.check.iter().cloned());
+        if cli.lint {
+            vars.push(env::set_upg_lint());
+        }
+        run("cargo", &args, &vars)?;
+
+        // Gather per-crate outputs to see the workspace as one graph.
+        merge::merge_workspace(out_dir, &self.workspace_name, &self.targets, self.compact())
+    }
+}
+
+fn run(cmd: &str, args: &[String], vars: &[(&str, String)]) -> Result<()> {
+    let mut command = Command::new(cmd);
+
+    let _span = debug_span!("run", cmd, ?args, ?vars,).entered();
+
+    let status = command
+        .args(args)
+        .envs(vars.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| "Failed to spawn a cmd process")?
+        .wait()
+        .with_context(|| "Failed to wait a cmd process")?;
+
+    ensure!(status.success(), "Process aborts.");
+
+    Ok(())
+}
+
+fn build_core(args: Vec<String>) -> Result<()> {
+    run(&ENV.UPG_DRIVER, &args, &[])
+}
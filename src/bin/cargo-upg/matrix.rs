@@ -0,0 +1,257 @@
+//! Analyze crates under several configurations, and compare the unsafety surface of them.
+//!
+//! Configurations are the `[[matrix]]` array in `upg.toml`. Each configuration is written to
+//! `matrix/<name>` in the output directory, and the comparison to `matrix.json`.
+
+use crate::{
+    Result,
+    merge::{is_crate_output, write_json},
+    surface::Surface,
+    target::Targets,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The directory name for outputs of configurations.
+pub const MATRIX_DIR: &str = "matrix";
+
+/// A configuration in the `[[matrix]]` array of `upg.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The name of the configuration, which is also the output directory name.
+    pub name: String,
+    /// Build for the target triple instead of the one from CLI.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Features to activate in addition to the ones from CLI.
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub no_default_features: bool,
+    #[serde(default)]
+    pub all_features: bool,
+    /// Extra `--cfg` passed to rustc for all crates, like `ktest` or `feature="foo"`.
+    #[serde(default)]
+    pub cfg: Vec<String>,
+    /// Extra arguments passed to `cargo build`.
+    #[serde(default)]
+    pub cargo_args: Vec<String>,
+}
+
+impl Config {
+    /// Arguments passed to `cargo build` except the target triple.
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for features in &self.features {
+            args.extend(["--features".to_owned(), features.clone()]);
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_owned());
+        }
+        if self.all_features {
+            args.push("--all-features".to_owned());
+        }
+        args.extend(self.cargo_args.iter().cloned());
+        args
+    }
+
+    pub fn rustc_flags(&self) -> impl Iterator<Item = String> {
+        self.cfg.iter().map(|cfg| format!("--cfg={cfg}"))
+    }
+}
+
+pub fn check_configs(configs: &[Config]) -> Result<()> {
+    ensure!(
+        !configs.is_empty(),
+        "No configuration is in the `[[matrix]]` array of upg.toml."
+    );
+    let mut names = BTreeSet::new();
+    for config in configs {
+        let name = &config.name;
+        ensure!(
+            !name.is_empty() && !name.contains(['/', '\\']) && name != "." && name != "..",
+            "{name:?} is not a valid configuration name."
+        );
+        ensure!(names.insert(name), "Configuration {name:?} is duplicated.");
+    }
+    Ok(())
+}
+
+/// Read outputs of target crates in the directory of a configuration.
+fn read_surface(dir: &Path, targets: &Targets) -> Result<Surface> {
+    let mut surface = Surface::default();
+    for krate in fs::read_dir(dir)? {
+        let krate = krate?;
+        let crate_name = krate.file_name().to_string_lossy().into_owned();
+        if krate.file_type()?.is_dir() && is_crate_output(&crate_name, targets) {
+            surface.read_crate(&krate.path())?;
+        }
+    }
+    Ok(surface)
+}
+
+/// Items present in some but not all configurations. Values are names of the configurations
+/// where the item is present.
+#[derive(Debug, Default, Serialize)]
+pub struct Comparison {
+    pub configs: Vec<String>,
+    pub functions: BTreeMap<String, Vec<String>>,
+    /// Caller to unsafe callee to configurations.
+    pub unsafe_callees: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    /// Function to tag name to configurations.
+    pub tags: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl Comparison {
+    fn new(surfaces: &[(&str, Surface)]) -> Self {
+        let configs: Vec<_> = surfaces.iter().map(|(name, _)| name.to_string()).collect();
+        let mut cmp = Comparison {
+            configs,
+            ..Default::default()
+        };
+
+        for (item, present) in partial(surfaces, |s| &s.functions) {
+            cmp.functions.insert(item.clone(), present);
+        }
+        for ((caller, callee), present) in partial(surfaces, |s| &s.unsafe_callees) {
+            let callees = cmp.unsafe_callees.entry(caller.clone()).or_default();
+            callees.insert(callee.clone(), present);
+        }
+        for ((name, tag), present) in partial(surfaces, |s| &s.tags) {
+            let tags = cmp.tags.entry(name.clone()).or_default();
+            tags.insert(tag.clone(), present);
+        }
+        cmp
+    }
+}
+
+/// Items not present in all surfaces, with configurations where they are present.
+fn partial<'s, T: Ord + 's>(
+    surfaces: &'s [(&str, Surface)],
+    items: impl Fn(&'s Surface) -> &'s BTreeSet<T>,
+) -> BTreeMap<&'s T, Vec<String>> {
+    let mut map = BTreeMap::<&T, Vec<String>>::new();
+    for (name, surface) in surfaces {
+        for item in items(surface) {
+            map.entry(item).or_default().push(name.to_string());
+        }
+    }
+    map.retain(|_, present| present.len() != surfaces.len());
+    map
+}
+
+/// The output directory of a configuration.
+pub fn config_dir(out_dir: &Path, config: &Config) -> PathBuf {
+    out_dir.join(MATRIX_DIR).join(&config.name)
+}
+
+/// Compare outputs of all configurations, and write `matrix.json` to the output directory.
+pub fn compare(
+    out_dir: &Path,
+    configs: &[Config],
+    targets: &Targets,
+    compact: bool,
+) -> Result<Comparison> {
+    let mut surfaces = Vec::with_capacity(configs.len());
+    for config in configs {
+        let surface = read_surface(&config_dir(out_dir, config), targets)?;
+        surfaces.push((config.name.as_str(), surface));
+    }
+    let cmp = Comparison::new(&surfaces);
+
+    let path = out_dir.join("matrix.json");
+    write_json(&path, &serde_json::to_value(&cmp)?, compact)?;
+    info!("{path:?} is written.");
+    Ok(cmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface(functions: &[&str], tags: &[(&str, &str)]) -> Surface {
+        Surface {
+            functions: functions.iter().map(|f| f.to_string()).collect(),
+            tags: tags
+                .iter()
+                .map(|(f, tag)| (f.to_string(), tag.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn partial_items() {
+        let surfaces = [
+            ("a", surface(&["f", "g"], &[])),
+            ("b", surface(&["f"], &[])),
+            ("c", surface(&["f", "h"], &[])),
+        ];
+        let map = partial(&surfaces, |s| &s.functions);
+        // f is in all configurations.
+        let map: Vec<_> = map.iter().map(|(f, v)| (f.as_str(), v.join(","))).collect();
+        assert_eq!(map, [("g", "a".to_owned()), ("h", "c".to_owned())]);
+    }
+
+    #[test]
+    fn comparison() {
+        let surfaces = [
+            ("a", surface(&["f"], &[("f", "Align"), ("f", "Init")])),
+            ("b", surface(&["f"], &[("f", "Align")])),
+        ];
+        let cmp = Comparison::new(&surfaces);
+        assert_eq!(cmp.configs, ["a", "b"]);
+        assert!(cmp.functions.is_empty());
+        assert!(cmp.unsafe_callees.is_empty());
+        assert_eq!(cmp.tags.len(), 1);
+        assert_eq!(
+            cmp.tags["f"],
+            [("Init".to_owned(), vec!["a".to_owned()])].into()
+        );
+    }
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn configs() {
+        let a = config(
+            r#"
+            name = "a"
+            features = ["x", "y"]
+            no-default-features = true
+            cfg = ["ktest"]
+            cargo-args = ["--locked"]
+            "#,
+        );
+        let args = [
+            "--features",
+            "x",
+            "--features",
+            "y",
+            "--no-default-features",
+            "--locked",
+        ];
+        assert_eq!(a.cargo_args(), args);
+        assert_eq!(a.rustc_flags().collect::<Vec<_>>(), ["--cfg=ktest"]);
+        assert!(toml::from_str::<Config>("name = \"a\"\nunknown = 1").is_err());
+
+        let b = config(r#"name = "b""#);
+        assert!(b.cargo_args().is_empty());
+        assert!(check_configs(&[a, b]).is_ok());
+        assert!(check_configs(&[]).is_err());
+        assert!(check_configs(&[config(r#"name = "a""#), config(r#"name = "a""#)]).is_err());
+        for name in ["", ".", "..", "a/b"] {
+            let config = config(&format!("name = {name:?}"));
+            assert!(check_configs(&[config]).is_err(), "{name:?}");
+        }
+        let dir = config_dir(Path::new("out"), &config(r#"name = "a""#));
+        assert_eq!(dir, Path::new("out/matrix/a"));
+    }
+}
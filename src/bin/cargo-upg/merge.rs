@@ -0,0 +1,250 @@
+//! Merge outputs of analyzed crates into `_workspace` in the output directory.
+
+use crate::{Result, target::Targets};
+use eyre::Context;
+use serde_json::{Map, Value, json};
+use std::{fs, path::Path};
+
+/// The directory name for merged outputs.
+const WORKSPACE_DIR: &str = "_workspace";
+
+/// Directories in the output directory which are not crate outputs, even if a target pattern
+/// like `*` matches them.
+const RESERVED_DIRS: &[&str] = &[WORKSPACE_DIR, crate::matrix::MATRIX_DIR];
+
+/// Merge `navi/tree.json`, `navi/unsafe_fns.json`, and `ids.json` of analyzed crates.
+///
+/// The merged tree has a root named after the workspace, with crate trees as subtrees.
+/// Node ids are renumbered depth first, so they are still the indices in the whole tree.
+/// Stable ids are unique across crates, so they are kept as is.
+pub fn merge_workspace(
+    out_dir: &Path,
+    workspace_name: &str,
+    targets: &Targets,
+    compact: bool,
+) -> Result<()> {
+    let mut crates = Vec::new();
+    for entry in fs::read_dir(out_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let navi = entry.path().join("navi");
+        // Outputs of crates not selected in this run may exist from previous runs.
+        if is_crate_output(&name, targets) && navi.join("tree.json").exists() {
+            crates.push((name, navi));
+        }
+    }
+    if crates.is_empty() {
+        return Ok(());
+    }
+    // Keep the output stable.
+    crates.sort_unstable();
+
+    let mut sub = Vec::with_capacity(crates.len());
+    let mut items = Map::new();
+    let mut unsafe_fns = Map::new();
+    let mut ids = Map::new();
+    let mut stats = Map::new();
+    // The root takes id 0.
+    let mut offset = 1;
+    for (_, navi) in &crates {
+        let mut navi_tree = read_json(&navi.join("tree.json"))?;
+
+        let mut tree = navi_tree["tree"].take();
+        let len = shift_id(&mut tree, offset);
+        add_stats(&mut stats, &tree["stats"]);
+        sub.push(tree);
+
+        if let Value::Object(map) = navi_tree["items"].take() {
+            for (stable_id, mut item) in map {
+                let id = item["id"].as_u64().unwrap_or_default() + offset;
+                item["id"] = id.into();
+                items.insert(stable_id, item);
+            }
+        }
+        offset += len;
+
+        let unsafe_fns_path = navi.join("unsafe_fns.json");
+        if unsafe_fns_path.exists()
+            && let Value::Object(map) = read_json(&unsafe_fns_path)?
+        {
+            unsafe_fns.extend(map);
+        }
+
+        // ids.json is in the crate directory.
+        let ids_path = navi.with_file_name("ids.json");
+        if ids_path.exists()
+            && let Value::Object(map) = read_json(&ids_path)?
+        {
+            ids.extend(map);
+        }
+    }
+    // Keys are stable ids, so sort by function names like the driver does.
+    let mut unsafe_fns: Vec<_> = unsafe_fns.into_iter().collect();
+    unsafe_fns.sort_by(|(id1, f1), (id2, f2)| {
+        (f1["name"].as_str(), id1).cmp(&(f2["name"].as_str(), id2))
+    });
+    let unsafe_fns: Map<_, _> = unsafe_fns.into_iter().collect();
+
+    let tree = json!({
+        "tree": {
+            "node": { "kind": "Mod", "name": workspace_name, "id": 0 },
+            "stats": stats,
+            "sub": sub,
+        },
+        "items": items,
+    });
+
+    let dir = out_dir.join(WORKSPACE_DIR).join("navi");
+    fs::create_dir_all(&dir)?;
+    write_json(&dir.join("tree.json"), &tree, compact)?;
+    write_json(
+        &dir.join("unsafe_fns.json"),
+        &Value::Object(unsafe_fns),
+        compact,
+    )?;
+    let ids_path = out_dir.join(WORKSPACE_DIR).join("ids.json");
+    write_json(&ids_path, &Value::Object(ids), compact)?;
+    info!(?crates, "{dir:?} is written.");
+    Ok(())
+}
+
+/// Whether the directory in the output directory holds the output of a target crate.
+pub fn is_crate_output(dir_name: &str, targets: &Targets) -> bool {
+    !RESERVED_DIRS.contains(&dir_name) && targets.is_target_crate(dir_name)
+}
+
+/// Add the offset to ids in the tree, and return the number of nodes.
+fn shift_id(tree: &mut Value, offset: u64) -> u64 {
+    let id = &mut tree["node"]["id"];
+    *id = (id.as_u64().unwrap_or_default() + offset).into();
+    let mut len = 1;
+    if let Value::Array(sub) = &mut tree["sub"] {
+        for subtree in sub {
+            len += shift_id(subtree, offset);
+        }
+    }
+    len
+}
+
+/// Sum up counts of subtree stats.
+fn add_stats(total: &mut Map<String, Value>, stats: &Value) {
+    if let Value::Object(stats) = stats {
+        for (key, count) in stats {
+            let sum = total.get(key).and_then(Value::as_u64).unwrap_or_default();
+            let count = count.as_u64().unwrap_or_default();
+            total.insert(key.clone(), (sum + count).into());
+        }
+    }
+}
+
+pub fn read_json(path: &Path) -> Result<Value> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+pub fn write_json(path: &Path, value: &Value, compact: bool) -> Result<()> {
+    let writer = fs::File::create(path)?;
+    if compact {
+        serde_json::to_writer(writer, value)?;
+    } else {
+        serde_json::to_writer_pretty(writer, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shift_ids() {
+        // Ids are in preorder, so the child of 1 is 2.
+        let mut tree = json!({
+            "node": { "id": 0 },
+            "sub": [
+                { "node": { "id": 1 }, "sub": [{ "node": { "id": 2 } }] },
+                { "node": { "id": 3 }, "sub": [] },
+            ],
+        });
+        assert_eq!(shift_id(&mut tree, 5), 4);
+        let ids = [
+            &tree["node"]["id"],
+            &tree["sub"][0]["node"]["id"],
+            &tree["sub"][0]["sub"][0]["node"]["id"],
+            &tree["sub"][1]["node"]["id"],
+        ];
+        assert_eq!(ids.map(|id| id.as_u64().unwrap()), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn stats() {
+        let mut total = Map::new();
+        add_stats(&mut total, &json!({ "Fn": 2, "Struct": 1 }));
+        add_stats(&mut total, &json!({ "Fn": 3 }));
+        add_stats(&mut total, &Value::Null);
+        assert_eq!(Value::Object(total), json!({ "Fn": 5, "Struct": 1 }));
+    }
+
+    fn write_crate(out_dir: &Path, krate: &str, fn_name: &str) {
+        let navi = out_dir.join(krate).join("navi");
+        fs::create_dir_all(&navi).unwrap();
+        let id = format!("{krate}-id");
+        let tree = json!({
+            "tree": {
+                "node": { "kind": "Mod", "name": krate, "id": 0 },
+                "stats": { "Fn": 1 },
+                "sub": [{ "node": { "kind": "Fn", "name": fn_name, "id": 1 }, "sub": [] }],
+            },
+            "items": { &id: { "name": fn_name, "id": 1 } },
+        });
+        write_json(&navi.join("tree.json"), &tree, true).unwrap();
+        let unsafe_fns = json!({ id: { "name": fn_name, "kind": "Caller" } });
+        write_json(&navi.join("unsafe_fns.json"), &unsafe_fns, true).unwrap();
+    }
+
+    #[test]
+    fn merge() {
+        let out_dir = std::env::temp_dir().join(format!("upg-merge-{}", std::process::id()));
+        _ = fs::remove_dir_all(&out_dir);
+        write_crate(&out_dir, "b", "b::g");
+        write_crate(&out_dir, "a", "a::f");
+        // Not selected in this run, and reserved directories even if matched by `*`.
+        write_crate(&out_dir, "c", "c::h");
+        write_crate(&out_dir, crate::matrix::MATRIX_DIR, "m::f");
+        let targets = Targets {
+            patterns: vec!["[ab]".to_owned(), "_*".to_owned(), "matrix".to_owned()],
+        };
+        assert!(is_crate_output("a", &targets));
+        assert!(!is_crate_output("c", &targets));
+        assert!(!is_crate_output(WORKSPACE_DIR, &targets));
+        assert!(!is_crate_output(crate::matrix::MATRIX_DIR, &targets));
+
+        merge_workspace(&out_dir, "ws", &targets, false).unwrap();
+        // Merge again to check the merged output is not merged into itself.
+        merge_workspace(&out_dir, "ws", &targets, false).unwrap();
+
+        let navi = out_dir.join(WORKSPACE_DIR).join("navi");
+        let tree = read_json(&navi.join("tree.json")).unwrap();
+        assert_eq!(tree["tree"]["node"]["name"], "ws");
+        assert_eq!(tree["tree"]["stats"], json!({ "Fn": 2 }));
+        let names: Vec<_> = tree["tree"]["sub"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|sub| (sub["node"]["name"].clone(), sub["node"]["id"].clone()))
+            .collect();
+        assert_eq!(names, [(json!("a"), json!(1)), (json!("b"), json!(3))]);
+        let items = json!({
+            "a-id": { "name": "a::f", "id": 2 },
+            "b-id": { "name": "b::g", "id": 4 },
+        });
+        assert_eq!(tree["items"], items);
+        let unsafe_fns = read_json(&navi.join("unsafe_fns.json")).unwrap();
+        let expected = json!({
+            "a-id": { "name": "a::f", "kind": "Caller" },
+            "b-id": { "name": "b::g", "kind": "Caller" },
+        });
+        assert_eq!(unsafe_fns, expected);
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+//! Unsafety surface read back from JSONs written by the driver, for comparison of outputs.
+
+use crate::{Result, merge::read_json};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// Unsafety surface of one or more crates.
+#[derive(Debug, Default)]
+pub struct Surface {
+    pub functions: BTreeSet<String>,
+    /// Functions declared as unsafe.
+    pub unsafe_fns: BTreeSet<String>,
+    /// Caller to unsafe callee. Implicit callees like drop glue are excluded.
+    pub unsafe_callees: BTreeSet<(String, String)>,
+    /// Function to tag name.
+    pub tags: BTreeSet<(String, String)>,
+    /// Function to `Unsafe` kind in `navi/unsafe_fns.json`.
+    pub unsafe_kinds: BTreeMap<String, String>,
+    /// Adt to field to the function writing the field.
+    pub field_writers: BTreeSet<(String, String, String)>,
+}
+
+impl Surface {
+    /// Read the output of a crate, i.e. `$UPG_DIR/<crate>`.
+    pub fn read_crate(&mut self, dir: &Path) -> Result<()> {
+        for item in fs::read_dir(dir)? {
+            let path = item?.path().join("caller.json");
+            if path.exists() {
+                self.add_caller(&read_json(&path)?);
+            }
+        }
+
+        let path = dir.join("navi").join("unsafe_fns.json");
+        if path.exists()
+            && let Value::Object(map) = read_json(&path)?
+        {
+            // Keyed by stable ids.
+            for unsafe_fn in map.values() {
+                if let (Some(name), Some(kind)) =
+                    (unsafe_fn["name"].as_str(), unsafe_fn["kind"].as_str())
+                {
+                    self.unsafe_kinds.insert(name.to_owned(), kind.to_owned());
+                }
+            }
+        }
+
+        let adt_dir = dir.join("adt");
+        if adt_dir.exists() {
+            for adt in fs::read_dir(adt_dir)? {
+                self.add_adt(&read_json(&adt?.path())?);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_caller(&mut self, caller: &Value) {
+        let Some(name) = caller["name"].as_str() else {
+            return;
+        };
+        self.functions.insert(name.to_owned());
+        if caller["safe"] == false {
+            self.unsafe_fns.insert(name.to_owned());
+        }
+        for info in caller["callees"]
+            .as_object()
+            .into_iter()
+            .flat_map(|m| m.values())
+        {
+            // Implicit callees like drop glue are not written by users, so only count callees
+            // with explicit instances.
+            let explicit = info["instance_name"]
+                .as_array()
+                .is_some_and(|v| !v.is_empty());
+            if info["safe"] == false
+                && explicit
+                && let Some(callee) = info["name"].as_str()
+            {
+                self.unsafe_callees
+                    .insert((name.to_owned(), callee.to_owned()));
+            }
+        }
+        for tag in caller["tags"].as_array().into_iter().flatten() {
+            if let Some(tag) = tag.as_str() {
+                self.tags.insert((name.to_owned(), tag.to_owned()));
+            }
+        }
+    }
+
+    fn add_adt(&mut self, adt: &Value) {
+        let Some(name) = adt["name"].as_str() else {
+            return;
+        };
+        let fields: Vec<_> = adt["variant_fields"]
+            .as_object()
+            .into_iter()
+            .flat_map(|map| map.values())
+            .map(|field| field["name"].as_str().unwrap_or_default())
+            .collect();
+        let access_field = adt["access_field"].as_array().into_iter().flatten();
+        for (idx, access) in access_field.enumerate() {
+            // Fields are in the same order as access.
+            let field = match fields.get(idx) {
+                Some(field) => field.to_string(),
+                None => idx.to_string(),
+            };
+            for writer in access["write"].as_array().into_iter().flatten() {
+                if let Some(writer) = writer["name"].as_str() {
+                    let key = (name.to_owned(), field.clone(), writer.to_owned());
+                    self.field_writers.insert(key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn caller() {
+        let mut surface = Surface::default();
+        surface.add_caller(&json!({
+            "name": "f",
+            "safe": false,
+            "tags": ["ValidPtr"],
+            "callees": {
+                "id_g": { "name": "g", "safe": false, "instance_name": ["g"] },
+                // Only called implicitly.
+                "id_drop": { "name": "drop", "safe": false, "instance_name": [] },
+                "id_h": { "name": "h", "safe": true, "instance_name": ["h"] },
+            },
+        }));
+        // No name.
+        surface.add_caller(&json!({ "safe": false }));
+
+        assert_eq!(surface.functions, ["f".to_owned()].into());
+        assert_eq!(surface.unsafe_fns, ["f".to_owned()].into());
+        let callee = ("f".to_owned(), "g".to_owned());
+        assert_eq!(surface.unsafe_callees, [callee].into());
+        let tag = ("f".to_owned(), "ValidPtr".to_owned());
+        assert_eq!(surface.tags, [tag].into());
+    }
+
+    #[test]
+    fn adt() {
+        let mut surface = Surface::default();
+        surface.add_adt(&json!({
+            "name": "S",
+            "variant_fields": { "0": { "name": "x" } },
+            "access_field": [
+                { "write": [{ "name": "f", "stable_id": "1" }] },
+                { "write": [{ "name": "g", "stable_id": "2" }] },
+            ],
+        }));
+        let writers: Vec<_> = surface.field_writers.into_iter().collect();
+        // The field without a name falls back to the index.
+        let expected = [("S", "1", "g"), ("S", "x", "f")]
+            .map(|(a, b, c)| (a.to_owned(), b.to_owned(), c.to_owned()));
+        assert_eq!(writers, expected);
+    }
+}
@@ -0,0 +1,206 @@
+//! Select crates to be analyzed by the driver. Other crates are compiled by plain rustc.
+
+use crate::Result;
+use clap::Args;
+use eyre::Context;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Used when neither CLI flags nor `upg.toml` select any crate.
+const DEFAULT_CRATES: &[&str] = &["core", "std", "alloc", "demo", "ostd"];
+
+const UPG_TOML: &str = "upg.toml";
+
+/// The env var to pass resolved targets from `cargo upg` to the rustc wrapper.
+const UPG_TARGETS: &str = "UPG_TARGETS";
+
+/// Crate selection from CLI, which is merged with the `[targets]` table in `upg.toml`.
+#[derive(Args, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Selection {
+    /// Crate names or glob patterns like `ostd*`. Dashes are equivalent to underscores.
+    #[arg(long = "crate", value_name = "NAME")]
+    pub crates: Vec<String>,
+    /// Analyze all workspace members.
+    #[arg(long)]
+    pub workspace: bool,
+    /// Analyze all path dependencies, including workspace members.
+    #[arg(long)]
+    pub path_deps: bool,
+}
+
+/// `upg.toml` in the current directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpgToml {
+    pub targets: Selection,
+    /// Configurations to analyze and compare with `--matrix`.
+    pub matrix: Vec<crate::matrix::Config>,
+}
+
+impl UpgToml {
+    /// Read `upg.toml` if it exists.
+    pub fn read() -> Result<Self> {
+        let path = Path::new(UPG_TOML);
+        if !path.exists() {
+            return Ok(UpgToml::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse {UPG_TOML}"))
+    }
+}
+
+impl Selection {
+    /// Merge the selection with the `[targets]` table in `upg.toml`.
+    pub fn merge(&mut self, other: Selection) {
+        let Selection {
+            crates,
+            workspace,
+            path_deps,
+        } = other;
+        self.crates.extend(crates);
+        self.workspace |= workspace;
+        self.path_deps |= path_deps;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.crates.is_empty() && !self.workspace && !self.path_deps
+    }
+
+    /// Expand workspace members and path dependencies to crate names via `cargo metadata`.
+    pub fn resolve(self) -> Result<Targets> {
+        if self.is_empty() {
+            let patterns = DEFAULT_CRATES.iter().map(|s| s.to_string()).collect();
+            return Ok(Targets { patterns });
+        }
+
+        let mut patterns: Vec<_> = self.crates.iter().map(|s| normalize(s)).collect();
+        if self.workspace || self.path_deps {
+            let metadata = cargo_metadata(&[])?;
+            let members = metadata["workspace_members"].as_array();
+            for pkg in metadata["packages"].as_array().into_iter().flatten() {
+                let is_member = members.is_some_and(|v| v.contains(&pkg["id"]));
+                // Path dependencies have no source like crates.io or git.
+                let is_path = pkg["source"].is_null();
+                if (self.workspace && is_member) || (self.path_deps && is_path) {
+                    // Library name may differ from the package name, so take all targets.
+                    for target in pkg["targets"].as_array().into_iter().flatten() {
+                        if let Some(name) = target["name"].as_str() {
+                            patterns.push(normalize(name));
+                        }
+                    }
+                }
+            }
+        }
+        patterns.sort_unstable();
+        patterns.dedup();
+        Ok(Targets { patterns })
+    }
+}
+
+/// A path like `target_directory` and `workspace_root` in `cargo metadata --no-deps`.
+pub fn metadata_path(metadata: &serde_json::Value, key: &str) -> Result<PathBuf> {
+    match metadata[key].as_str() {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => bail!("{key} is not found in `cargo metadata`"),
+    }
+}
+
+pub fn cargo_metadata(args: &[&str]) -> Result<serde_json::Value> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version=1"])
+        .args(args)
+        .output()
+        .with_context(|| "Failed to run `cargo metadata`")?;
+    ensure!(
+        output.status.success(),
+        "`cargo metadata` failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Crate names are always in snake case in rustc.
+fn normalize(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Resolved crate names or glob patterns.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Targets {
+    pub patterns: Vec<String>,
+}
+
+impl Targets {
+    pub fn set_env(&self) -> Result<(&'static str, String)> {
+        Ok((UPG_TARGETS, serde_json::to_string(self)?))
+    }
+
+    /// Read targets set by `cargo upg`.
+    pub fn from_env() -> Result<Self> {
+        let json =
+            std::env::var(UPG_TARGETS).with_context(|| format!("{UPG_TARGETS} is not set"))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn is_target_crate(&self, crate_name: &str) -> bool {
+        self.patterns.iter().any(|pat| match Pattern::new(pat) {
+            Ok(glob) => glob.matches(crate_name),
+            Err(_) => pat == crate_name,
+        })
+    }
+}
+
+/// The value of `--crate-name` passed to rustc.
+pub fn crate_name(rustc_args: &[String]) -> Option<&str> {
+    let pos = rustc_args.iter().position(|arg| arg == "--crate-name")?;
+    rustc_args.get(pos + 1).map(|s| s.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(crates: &[&str]) -> Targets {
+        let selection = Selection {
+            crates: crates.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        selection.resolve().unwrap()
+    }
+
+    #[test]
+    fn normalize_names() {
+        assert_eq!(normalize("safety-tool"), "safety_tool");
+        assert_eq!(normalize("ostd"), "ostd");
+        // Patterns are normalized, sorted and deduplicated.
+        let targets = targets(&["a-b", "a_b", "ostd-*"]);
+        assert_eq!(targets.patterns, ["a_b", "ostd_*"]);
+    }
+
+    #[test]
+    fn target_crates() {
+        let targets = targets(&["ostd*", "core", "[bad"]);
+        assert!(targets.is_target_crate("ostd"));
+        assert!(targets.is_target_crate("ostd_macros"));
+        assert!(targets.is_target_crate("core"));
+        assert!(!targets.is_target_crate("core_arch"));
+        // An invalid glob pattern is compared as is.
+        assert!(targets.is_target_crate("[bad"));
+
+        let default = Selection::default().resolve().unwrap();
+        assert!(default.is_target_crate("std"));
+        assert!(!default.is_target_crate("libc"));
+    }
+
+    #[test]
+    fn rustc_crate_name() {
+        let args = ["--edition=2021", "--crate-name", "ostd", "src/lib.rs"].map(String::from);
+        assert_eq!(crate_name(&args), Some("ostd"));
+        assert_eq!(crate_name(&args[..2]), None);
+    }
+}
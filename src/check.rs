@@ -0,0 +1,397 @@
+//! Check mode to gate CI: compare the crate against a baseline, and fail on new unsafe calls,
+//! unsafe blocks, or raw field writes without safety tags or safety comments.
+//!
+//! * `UPG_BASELINE`: the directory of baselines, which has a `<crate>.json` for each crate.
+//! * `UPG_BLESS`: write current findings to the baseline instead of checking.
+//! * `UPG_ALLOWLIST`: a file of findings to ignore. Each line is a function name, or a finding
+//!   like `caller -> callee` and `caller -> Adt.field`. Glob patterns are supported, and lines
+//!   starting with `#` are comments.
+
+use crate::{
+    FxIndexMap,
+    info_fn::FnInfo,
+    output::utils::{is_safe, name, stable_id, tag_names},
+};
+use rustc_hir::{
+    Block, BlockCheckMode, HirId, Node, UnsafeSource,
+    def_id::DefId,
+    intravisit::{self, Visitor},
+};
+use rustc_middle::ty::TyCtxt;
+use rustc_public::{CrateDef, rustc_internal::internal, ty::FnDef};
+use rustc_span::Span;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Findings without safety tags or comments. Findings with them are never recorded.
+///
+/// Findings are keyed by stable ids, so items sharing a name are told apart, and map to the
+/// names to keep the baseline readable.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Baseline {
+    /// `caller -> callee` in stable ids to the names.
+    unsafe_calls: BTreeMap<String, String>,
+    /// Stable id of the function to the number of unsafe blocks.
+    unsafe_blocks: BTreeMap<String, UnsafeBlocks>,
+    /// `caller -> Adt.field` in stable ids of the caller and the adt to the names.
+    raw_field_writes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UnsafeBlocks {
+    name: String,
+    count: usize,
+}
+
+impl Baseline {
+    fn read(path: &Path, tcx: TyCtxt) -> Self {
+        if !path.exists() {
+            return Baseline::default();
+        }
+        let text = fs::read_to_string(path).unwrap_or_else(|err| {
+            tcx.dcx()
+                .fatal(format!("failed to read the baseline {path:?}: {err}"))
+        });
+        serde_json::from_str(&text).unwrap_or_else(|err| {
+            tcx.dcx()
+                .fatal(format!("failed to parse the baseline {path:?}: {err}"))
+        })
+    }
+
+    fn write(&self, path: &Path, tcx: TyCtxt) {
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let file = fs::File::create(path)?;
+            serde_json::to_writer_pretty(file, self)?;
+            Ok(())
+        };
+        if let Err(err) = write() {
+            tcx.dcx()
+                .fatal(format!("failed to write the baseline {path:?}: {err}"));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FindingKind {
+    UnsafeCall,
+    UnsafeBlock,
+    RawFieldWrite,
+}
+
+struct Finding {
+    kind: FindingKind,
+    caller: Item,
+    /// The unsafe callee or the written field.
+    target: Option<Item>,
+    /// The unsafe block, the call, or the write.
+    span: Span,
+}
+
+/// A function or a field, like `Adt.field` with the stable id of the adt.
+struct Item {
+    name: String,
+    stable_id: String,
+}
+
+impl Item {
+    fn new<T: CrateDef + Copy>(item: T, tcx: TyCtxt) -> Self {
+        Item {
+            name: name(item, tcx),
+            stable_id: stable_id(item, tcx),
+        }
+    }
+}
+
+impl Finding {
+    /// The key in the allowlist.
+    fn key(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{} -> {}", self.caller.name, target.name),
+            None => self.caller.name.clone(),
+        }
+    }
+
+    /// The key in the baseline.
+    fn id(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{} -> {}", self.caller.stable_id, target.stable_id),
+            None => self.caller.stable_id.clone(),
+        }
+    }
+
+    fn report(&self, tcx: TyCtxt) {
+        let what = match self.kind {
+            FindingKind::UnsafeCall => "unsafe call",
+            FindingKind::UnsafeBlock => "unsafe block",
+            FindingKind::RawFieldWrite => "field write through a raw pointer",
+        };
+        let caller = &self.caller.name;
+        let item = match &self.target {
+            Some(target) => format!("`{caller}` -> `{}`", target.name),
+            None => format!("in `{caller}`"),
+        };
+        tcx.dcx()
+            .struct_span_err(
+                self.span,
+                format!("{what} without a safety tag or comment: {item}"),
+            )
+            .with_help(format!(
+                "add a safety tag to `{caller}`, a `// SAFETY:` comment to the unsafe block, \
+                 or an entry to the allowlist"
+            ))
+            .emit();
+    }
+}
+
+struct Allowlist {
+    /// Lines that are not glob patterns are escaped to match literally.
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Allowlist {
+    fn read(path: Option<PathBuf>, tcx: TyCtxt) -> Self {
+        let Some(path) = path else {
+            return Allowlist { patterns: vec![] };
+        };
+        let text = fs::read_to_string(&path).unwrap_or_else(|err| {
+            tcx.dcx()
+                .fatal(format!("failed to read the allowlist {path:?}: {err}"))
+        });
+        let patterns = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                glob::Pattern::new(line)
+                    .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(line)))
+                    .expect("an escaped pattern is valid")
+            })
+            .collect();
+        Allowlist { patterns }
+    }
+
+    fn allows(&self, finding: &Finding) -> bool {
+        let key = finding.key();
+        let matches = |pat: &glob::Pattern| pat.matches(&key) || pat.matches(&finding.caller.name);
+        self.patterns.iter().any(matches)
+    }
+}
+
+/// Run the check if `UPG_BASELINE` is set. New findings are emitted as errors, so the
+/// compilation fails.
+pub fn check(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) {
+    let Some(dir) = std::env::var_os("UPG_BASELINE") else {
+        return;
+    };
+    let crate_name = rustc_public::local_crate().name;
+    let path = PathBuf::from(dir).join(format!("{crate_name}.json"));
+
+    let findings = findings(map_fn, tcx);
+
+    if std::env::var("UPG_BLESS").is_ok_and(|s| s != "0") {
+        let mut baseline = Baseline::default();
+        for finding in &findings {
+            let (id, key) = (finding.id(), finding.key());
+            match finding.kind {
+                FindingKind::UnsafeCall => _ = baseline.unsafe_calls.insert(id, key),
+                FindingKind::UnsafeBlock => {
+                    let blocks = baseline.unsafe_blocks.entry(id).or_insert(UnsafeBlocks {
+                        name: key,
+                        count: 0,
+                    });
+                    blocks.count += 1;
+                }
+                FindingKind::RawFieldWrite => _ = baseline.raw_field_writes.insert(id, key),
+            }
+        }
+        baseline.write(&path, tcx);
+        tcx.dcx().note(format!("the baseline {path:?} is written"));
+        return;
+    }
+
+    let baseline = Baseline::read(&path, tcx);
+    let allowlist = Allowlist::read(std::env::var_os("UPG_ALLOWLIST").map(PathBuf::from), tcx);
+
+    // Unsafe blocks have no stable identity, so they are compared by numbers in a function.
+    let mut blocks = BTreeMap::<String, usize>::new();
+    for finding in &findings {
+        if let FindingKind::UnsafeBlock = finding.kind {
+            *blocks.entry(finding.id()).or_default() += 1;
+        }
+    }
+
+    let mut count = 0;
+    for finding in &findings {
+        let id = finding.id();
+        let in_baseline = match finding.kind {
+            FindingKind::UnsafeCall => baseline.unsafe_calls.contains_key(&id),
+            FindingKind::UnsafeBlock => {
+                let baselined = baseline.unsafe_blocks.get(&id).map_or(0, |b| b.count);
+                blocks[&id] <= baselined
+            }
+            FindingKind::RawFieldWrite => baseline.raw_field_writes.contains_key(&id),
+        };
+        if !in_baseline && !allowlist.allows(finding) {
+            finding.report(tcx);
+            count += 1;
+        }
+    }
+
+    if count != 0 {
+        tcx.dcx().err(format!(
+            "{count} new unsafe findings without safety tags or comments, \
+             compared to the baseline {path:?}"
+        ));
+    }
+}
+
+/// Unsafe calls, unsafe blocks, and raw field writes without safety tags or comments.
+/// Calls and writes inside an unsafe block with a safety comment are considered commented.
+fn findings(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (&fn_def, info) in map_fn {
+        if !tag_names(fn_def).is_empty() {
+            continue;
+        }
+        let caller = || Item::new(fn_def, tcx);
+
+        let mut commented = Vec::new();
+        for (block_id, block) in unsafe_blocks(fn_def, tcx) {
+            if has_safety_comment(block_id, block, tcx) {
+                commented.push(block);
+            } else {
+                findings.push(Finding {
+                    kind: FindingKind::UnsafeBlock,
+                    caller: caller(),
+                    target: None,
+                    span: block,
+                });
+            }
+        }
+        let is_commented = |span: Span| commented.iter().any(|block| block.contains(span));
+        // Uses of an unsafe fn other than calls, like a function pointer, are located at the
+        // caller.
+        let fn_span = tcx.def_span(internal(tcx, fn_def.def_id()));
+
+        for (&callee, callee_info) in &info.callees {
+            if !callee_info.is_explicit() || is_safe(callee) {
+                continue;
+            }
+            let mut spans: Vec<_> = callee_info
+                .calls
+                .iter()
+                .map(|call| internal(tcx, call.span))
+                .collect();
+            if spans.is_empty() {
+                spans.push(fn_span);
+            }
+            for span in spans {
+                if !is_commented(span) {
+                    findings.push(Finding {
+                        kind: FindingKind::UnsafeCall,
+                        caller: caller(),
+                        target: Some(Item {
+                            name: callee_info.non_instance_name.clone(),
+                            stable_id: stable_id(callee, tcx),
+                        }),
+                        span,
+                    });
+                }
+            }
+        }
+        for ((adt, field), spans) in &info.raw_field_writes {
+            for &span in spans {
+                let span = internal(tcx, span);
+                if !is_commented(span) {
+                    let adt = Item::new(*adt, tcx);
+                    findings.push(Finding {
+                        kind: FindingKind::RawFieldWrite,
+                        caller: caller(),
+                        target: Some(Item {
+                            name: format!("{}.{field}", adt.name),
+                            stable_id: format!("{}.{field}", adt.stable_id),
+                        }),
+                        span,
+                    });
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// User-written unsafe blocks in the function body. Blocks from macro expansions are skipped.
+pub fn unsafe_blocks(fn_def: FnDef, tcx: TyCtxt) -> Vec<(HirId, rustc_span::Span)> {
+    unsafe_blocks_internal(internal(tcx, fn_def.def_id()), tcx)
+}
+
+/// User-written unsafe blocks in the body of a function, static, or const.
+pub fn unsafe_blocks_internal(did: DefId, tcx: TyCtxt) -> Vec<(HirId, rustc_span::Span)> {
+    struct UnsafeBlocks(Vec<(HirId, rustc_span::Span)>);
+
+    impl<'tcx> Visitor<'tcx> for UnsafeBlocks {
+        fn visit_block(&mut self, block: &'tcx Block<'tcx>) {
+            if let BlockCheckMode::UnsafeBlock(UnsafeSource::UserProvided) = block.rules
+                && !block.span.from_expansion()
+            {
+                self.0.push((block.hir_id, block.span));
+            }
+            intravisit::walk_block(self, block);
+        }
+    }
+
+    let mut blocks = UnsafeBlocks(Vec::new());
+    if let Some(local) = did.as_local()
+        && let Some(body) = tcx.hir_maybe_body_owned_by(local)
+    {
+        blocks.visit_body(body);
+    }
+    blocks.0
+}
+
+/// A `// SAFETY:` comment above the unsafe block, or before it in the same line. The comment
+/// may also be above the statement containing the block, like `let x =` in the line before
+/// `unsafe {`.
+pub fn has_safety_comment(block_id: HirId, block: rustc_span::Span, tcx: TyCtxt) -> bool {
+    if is_commented(block, tcx) {
+        return true;
+    }
+    for (_, node) in tcx.hir_parent_iter(block_id) {
+        match node {
+            Node::Stmt(stmt) => return is_commented(stmt.span, tcx),
+            // The block is not in a statement, like the tail expression of a block.
+            Node::Block(_) | Node::Item(_) | Node::ImplItem(_) | Node::TraitItem(_) => break,
+            _ => (),
+        }
+    }
+    false
+}
+
+fn is_commented(span: rustc_span::Span, tcx: TyCtxt) -> bool {
+    let is_safety = |s: &str| s.to_ascii_uppercase().contains("SAFETY:");
+    let Ok(prev) = tcx.sess.source_map().span_to_prev_source(span) else {
+        return false;
+    };
+    let mut lines = prev.split('\n').rev();
+    if lines.next().is_some_and(is_safety) {
+        return true;
+    }
+    for line in lines.map(str::trim) {
+        let is_comment = line.starts_with("//") || line.starts_with("/*") || line.starts_with('*');
+        if is_comment && is_safety(line) {
+            return true;
+        } else if !is_comment && !line.starts_with("#[") {
+            break;
+        }
+    }
+    false
+}
@@ -0,0 +1,40 @@
+//! Recoverable errors on odd items, which are written to `diagnostics.json` instead of
+//! aborting the whole run.
+
+use serde::Serialize;
+use std::sync::Mutex;
+
+static DIAGNOSTICS: Mutex<Vec<Diagnostic>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum DiagnosticKind {
+    /// The item path doesn't start from the crate root, so it's put under `__phony`.
+    NonLocalRoot,
+    /// The item path is not found in the navigation tree, so it's put under `__phony`.
+    NodeNotFound,
+    /// A struct or union has more than one variant, and only the first one is kept. rustc
+    /// always gives them one variant, so this guards against changes of the compiler.
+    MultipleVariants,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// The name of the affected item.
+    pub item: String,
+    pub message: String,
+}
+
+pub fn report(kind: DiagnosticKind, item: impl Into<String>, message: String) {
+    let diagnostic = Diagnostic {
+        kind,
+        item: item.into(),
+        message,
+    };
+    DIAGNOSTICS.lock().unwrap().push(diagnostic);
+}
+
+/// All diagnostics reported so far.
+pub fn take() -> Vec<Diagnostic> {
+    std::mem::take(&mut DIAGNOSTICS.lock().unwrap())
+}
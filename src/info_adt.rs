@@ -1,17 +1,18 @@
 use crate::{
     adt::{Adt, AdtAccess},
     info_fn::FnInfo,
-    utils::{FxIndexMap, ThinVec},
+    utils::{FxIndexMap, FxIndexSet, ThinVec},
 };
 use rustc_hir::def_id::DefId;
 use rustc_middle::ty::{Ty, TyCtxt, TyKind};
 use rustc_public::{
     CrateDef,
     rustc_internal::internal,
-    ty::{AdtDef, FnDef},
+    ty::{AdtDef, FnDef, GenericArgs},
 };
 use serde::Serialize;
 
+/// Adt info is keyed by the generic adt, and instances are recorded in [`AdtInfo::instances`].
 pub fn adt_info(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) -> FxIndexMap<Adt, AdtInfo> {
     let mut map_adt =
         FxIndexMap::<Adt, AdtInfo>::with_capacity_and_hasher(map_fn.len(), Default::default());
@@ -19,7 +20,8 @@ pub fn adt_info(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) -> FxIndexMap<A
     for (&fn_def, fn_info) in map_fn {
         // Append the fn_def to adt map.
         for (adt, locals) in &fn_info.adts {
-            let adt_info = map_adt.entry(adt.clone()).or_default();
+            let adt_info = map_adt.entry(adt.generic()).or_default();
+            adt_info.instances.insert(adt.args.clone());
 
             for access in &locals.access {
                 let v = adt_info.map.entry(access.clone()).or_default();
@@ -35,7 +37,8 @@ pub fn adt_info(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) -> FxIndexMap<A
 
         // Append the constructor for adt.
         for adt in &fn_info.ret_adts {
-            let adt_info = map_adt.entry(adt.clone()).or_default();
+            let adt_info = map_adt.entry(adt.generic()).or_default();
+            adt_info.instances.insert(adt.args.clone());
             adt_info.constructors.push(fn_def);
         }
     }
@@ -62,6 +65,8 @@ pub struct AdtInfo {
     /// Functions that access the fields. The slice index corresponds to the field index.
     /// If the adt is not a struct, or unit struct (struct without field), the slices is empty.
     pub fields: Box<[Access]>,
+    /// Generic arguments the adt is instantiated with in all functions.
+    pub instances: FxIndexSet<GenericArgs>,
 }
 
 impl AdtInfo {
@@ -284,12 +289,15 @@ pub struct Field {
 
 #[derive(Clone, Debug, Serialize)]
 pub struct OutAdtFnKindInfo {
+    /// The adt name.
+    name: String,
     kind: AdtFnKind,
     field: OutFieldMap,
 }
 
-pub fn out_adt_fn_kind_info(map: &AdtFnKindInfo) -> OutAdtFnKindInfo {
+pub fn out_adt_fn_kind_info(map: &AdtFnKindInfo, name: String) -> OutAdtFnKindInfo {
     OutAdtFnKindInfo {
+        name,
         kind: map.kind,
         field: out_field_map(&map.field),
     }
@@ -304,10 +312,10 @@ pub fn out_field_map(map: &FieldMap) -> OutFieldMap {
 }
 
 pub type FnAdtMap = FxIndexMap<FnDef, AdtFnKindMap>;
-/// The outer key is caller FnDef, the inner key is Callee FnDef name string.
+/// The outer key is caller FnDef, the inner key is the generic callee FnDef.
 /// AdtFnKindMap only collects Adts that are accessed directly in the caller.
 /// We haven't push field access or interprcedural kinds.
-pub type CallerCalleeMap = FxIndexMap<FnDef, FxIndexMap<String, AdtFnKindMap>>;
+pub type CallerCalleeMap = FxIndexMap<FnDef, FxIndexMap<FnDef, AdtFnKindMap>>;
 
 #[derive(Default)]
 pub struct AdtFnCollector {
@@ -377,13 +385,11 @@ impl AdtFnCollector {
 
         for (caller, info) in map_fn {
             let map = caller_callee_map.entry(*caller).or_default();
-            for (callee, callee_info) in &info.callees {
-                // Callee is an Instance, but we strip the mono types, and use FnDef name
-                // as in output Function CalleeInfo.
+            for callee in info.callees.keys() {
+                // Callee is an Instance, but we strip the mono types, and use the generic
+                // FnDef as in output Function CalleeInfo.
                 if let Some(adt_fn_kind) = fn_adt_map.get(callee) {
-                    let adt_map = map
-                        .entry(callee_info.non_instance_name.clone())
-                        .or_default();
+                    let adt_map = map.entry(*callee).or_default();
                     for adt in info.adts.keys() {
                         // Ignore mono types.
                         let adt = adt.def;
@@ -0,0 +1,486 @@
+//! Call sites in a function body, and where the values passed to callees come from.
+
+use crate::{
+    analyze_fn_def::static_item,
+    check::unsafe_blocks_internal,
+    output::utils::{is_safe, name, src_from_span},
+    utils::{FxHashMap, FxHashSet, FxIndexSet},
+};
+use rustc_middle::ty::TyCtxt;
+use rustc_public::{
+    DefId,
+    mir::{
+        AssertMessage, BasicBlockIdx, Body, Local, Operand, Place, ProjectionElem, Rvalue,
+        StatementKind, TerminatorKind,
+    },
+    rustc_internal::internal,
+    ty::{FnDef, RigidTy, Span, Ty, TyKind, VariantIdx},
+};
+use serde::Serialize;
+
+/// A call terminator in the body.
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    pub span: Span,
+    /// The basic block terminated by the call.
+    pub bb: BasicBlockIdx,
+    /// The call is inside a user-written unsafe block.
+    pub in_unsafe: bool,
+    /// Sources of each argument passed to the callee. An empty list means the value is
+    /// computed in the body without mentioning caller arguments, calls, or constants.
+    pub args: Vec<Vec<ArgSource>>,
+    /// Branches and assertions dominating the call if the callee is unsafe, from the entry
+    /// of the body to the call.
+    pub guards: Vec<Guard>,
+}
+
+/// A `SwitchInt` or `Assert` terminator that must be passed in a certain way to reach a call.
+#[derive(Debug, Clone)]
+pub struct Guard {
+    pub kind: GuardKind,
+    pub span: Span,
+    pub bb: BasicBlockIdx,
+    /// The source of the condition, like `i < self.len` or `v[i]`.
+    pub src: String,
+    /// The branch taken to reach the call: `true` or `false` for booleans, the matched value,
+    /// or `otherwise`. For assertions, the expected value of the condition.
+    pub taken: String,
+    /// The panic message of a failed assertion.
+    pub msg: Option<&'static str>,
+    /// Sources of the condition.
+    pub sources: Vec<ArgSource>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum GuardKind {
+    SwitchInt,
+    Assert,
+}
+
+/// Where a value passed to a callee comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind")]
+pub enum ArgSource {
+    /// An argument of the caller, or a place reached from it like `(*self).len`.
+    /// The index starts from 0.
+    Arg {
+        index: usize,
+        place: String,
+    },
+    /// The return value of another call.
+    Ret {
+        callee: String,
+    },
+    /// The address of a static.
+    Static {
+        name: String,
+    },
+    Const,
+}
+
+/// Calls to function items in the body, in the order of basic blocks.
+/// Calls through function pointers or closures in locals are not included.
+pub fn call_sites(body: &Body, owner: DefId, tcx: TyCtxt) -> Vec<(FnDef, CallSite)> {
+    let unsafe_blocks = unsafe_blocks_internal(internal(tcx, owner), tcx);
+    let provenance = Provenance::new(body, tcx);
+    let preds = predecessors(body);
+    let idoms = dominators(body, &preds);
+
+    let mut calls = Vec::new();
+    for (bb, block) in body.blocks.iter().enumerate() {
+        let TerminatorKind::Call { func, args, .. } = &block.terminator.kind else {
+            continue;
+        };
+        let Ok(ty) = func.ty(body.locals()) else {
+            continue;
+        };
+        let TyKind::RigidTy(RigidTy::FnDef(fn_def, _)) = ty.kind() else {
+            continue;
+        };
+        let span = block.terminator.span;
+        let internal_span = internal(tcx, span);
+        let in_unsafe = unsafe_blocks
+            .iter()
+            .any(|&(_, block)| internal_span.find_ancestor_inside(block).is_some());
+        let args = args.iter().map(|arg| provenance.sources(arg)).collect();
+        let guards = if is_safe(fn_def) {
+            Vec::new()
+        } else {
+            guards(bb, body, &preds, &idoms, &provenance, tcx)
+        };
+        calls.push((
+            fn_def,
+            CallSite {
+                span,
+                bb,
+                in_unsafe,
+                args,
+                guards,
+            },
+        ));
+    }
+    calls
+}
+
+/// Terminators of dominators of the block that decide whether the block is reached.
+fn guards(
+    bb: BasicBlockIdx,
+    body: &Body,
+    preds: &[Vec<BasicBlockIdx>],
+    idoms: &[Option<BasicBlockIdx>],
+    provenance: &Provenance,
+    tcx: TyCtxt,
+) -> Vec<Guard> {
+    let mut guards = Vec::new();
+    let mut dom = idoms[bb];
+    while let Some(d) = dom {
+        dom = idoms[d];
+        let term = &body.blocks[d].terminator;
+        let (kind, cond, taken, msg) = match &term.kind {
+            TerminatorKind::SwitchInt { discr, targets } => {
+                let is_bool = discr
+                    .ty(body.locals())
+                    .is_ok_and(|ty| matches!(ty.kind(), TyKind::RigidTy(RigidTy::Bool)));
+                let branch = targets
+                    .branches()
+                    .map(|(value, target)| (Some(value), target))
+                    .chain([(None, targets.otherwise())])
+                    .find(|&(_, target)| {
+                        // The block is only reached through the edge to the target, so
+                        // other predecessors of the target must be back edges.
+                        dominates(target, bb, idoms)
+                            && preds[target]
+                                .iter()
+                                .all(|&pred| pred == d || dominates(target, pred, idoms))
+                    });
+                let taken = match branch {
+                    Some((Some(0), _)) if is_bool => "false".to_owned(),
+                    Some((None, _)) if is_bool => "true".to_owned(),
+                    Some((Some(value), _)) => value.to_string(),
+                    Some((None, _)) => "otherwise".to_owned(),
+                    // Both branches reach the call, so the switch doesn't guard it.
+                    None => continue,
+                };
+                (GuardKind::SwitchInt, discr, taken, None)
+            }
+            TerminatorKind::Assert {
+                cond,
+                expected,
+                msg,
+                ..
+            } => (
+                GuardKind::Assert,
+                cond,
+                expected.to_string(),
+                Some(assert_msg(msg)),
+            ),
+            _ => continue,
+        };
+        guards.push(Guard {
+            kind,
+            span: term.span,
+            bb: d,
+            src: src_from_span(term.span, tcx),
+            taken,
+            msg,
+            sources: provenance.sources(cond),
+        });
+    }
+    guards.reverse();
+    guards
+}
+
+/// The panic message of a failed assertion without formatted values.
+pub fn assert_msg(msg: &AssertMessage) -> &'static str {
+    match msg {
+        AssertMessage::BoundsCheck { .. } => "index out of bounds",
+        AssertMessage::MisalignedPointerDereference { .. } => "misaligned pointer dereference",
+        AssertMessage::NullPointerDereference => "null pointer dereference",
+        AssertMessage::InvalidEnumConstruction(_) => "invalid enum construction",
+        msg => msg.description().unwrap_or("assertion failed"),
+    }
+}
+
+/// Immediate dominators of basic blocks. The entry block and unreachable blocks have none.
+/// This is the iterative algorithm by Cooper, Harvey, and Kennedy.
+fn dominators(body: &Body, preds: &[Vec<BasicBlockIdx>]) -> Vec<Option<BasicBlockIdx>> {
+    let len = body.blocks.len();
+
+    // Reverse postorder from the entry block.
+    let mut postorder = Vec::with_capacity(len);
+    let mut visited = vec![false; len];
+    let mut stack = vec![(0, body.blocks[0].terminator.successors())];
+    visited[0] = true;
+    while let Some((bb, succs)) = stack.last_mut() {
+        if let Some(succ) = succs.pop() {
+            if !visited[succ] {
+                visited[succ] = true;
+                stack.push((succ, body.blocks[succ].terminator.successors()));
+            }
+        } else {
+            postorder.push(*bb);
+            stack.pop();
+        }
+    }
+    let mut order = vec![usize::MAX; len];
+    for (idx, &bb) in postorder.iter().rev().enumerate() {
+        order[bb] = idx;
+    }
+
+    let mut idoms = vec![None; len];
+    idoms[0] = Some(0);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &bb in postorder.iter().rev().skip(1) {
+            let mut new_idom: Option<BasicBlockIdx> = None;
+            for &pred in &preds[bb] {
+                if idoms[pred].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => pred,
+                    Some(mut other) => {
+                        let mut pred = pred;
+                        while pred != other {
+                            while order[pred] > order[other] {
+                                pred = idoms[pred].unwrap();
+                            }
+                            while order[other] > order[pred] {
+                                other = idoms[other].unwrap();
+                            }
+                        }
+                        pred
+                    }
+                });
+            }
+            if new_idom != idoms[bb] {
+                idoms[bb] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idoms[0] = None;
+    idoms
+}
+
+fn predecessors(body: &Body) -> Vec<Vec<BasicBlockIdx>> {
+    let mut preds = vec![Vec::new(); body.blocks.len()];
+    for (bb, block) in body.blocks.iter().enumerate() {
+        for succ in block.terminator.successors() {
+            preds[succ].push(bb);
+        }
+    }
+    preds
+}
+
+fn dominates(a: BasicBlockIdx, b: BasicBlockIdx, idoms: &[Option<BasicBlockIdx>]) -> bool {
+    let mut bb = Some(b);
+    while let Some(dom) = bb {
+        if dom == a {
+            return true;
+        }
+        bb = idoms[dom];
+    }
+    false
+}
+
+/// A definition of a whole local.
+enum Def<'a> {
+    Assign(&'a Rvalue),
+    /// The destination of a call to the named function.
+    Call(String),
+}
+
+/// Traces values backwards through assignments to find their sources.
+/// The analysis is flow-insensitive: all definitions of a local are considered.
+struct Provenance<'a, 'tcx> {
+    body: &'a Body,
+    defs: FxHashMap<Local, Vec<Def<'a>>>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx> Provenance<'a, 'tcx> {
+    fn new(body: &'a Body, tcx: TyCtxt<'tcx>) -> Self {
+        let mut defs = FxHashMap::<Local, Vec<Def>>::default();
+        for block in &body.blocks {
+            for stmt in &block.statements {
+                if let StatementKind::Assign(place, rvalue) = &stmt.kind
+                    && place.projection.is_empty()
+                {
+                    defs.entry(place.local)
+                        .or_default()
+                        .push(Def::Assign(rvalue));
+                }
+            }
+            if let TerminatorKind::Call {
+                func, destination, ..
+            } = &block.terminator.kind
+                && destination.projection.is_empty()
+                && let Ok(ty) = func.ty(body.locals())
+            {
+                let callee = match ty.kind() {
+                    TyKind::RigidTy(RigidTy::FnDef(fn_def, _)) => name(fn_def, tcx),
+                    _ => format!("{ty}"),
+                };
+                defs.entry(destination.local)
+                    .or_default()
+                    .push(Def::Call(callee));
+            }
+        }
+        Provenance { body, defs, tcx }
+    }
+
+    fn sources(&self, operand: &Operand) -> Vec<ArgSource> {
+        let mut sources = FxIndexSet::default();
+        let mut visited = FxHashSet::default();
+        self.operand(operand, &[], &mut sources, &mut visited);
+        sources.into_iter().collect()
+    }
+
+    /// Sources of the operand with extra projections applied to it.
+    fn operand(
+        &self,
+        operand: &Operand,
+        proj: &[ProjectionElem],
+        sources: &mut FxIndexSet<ArgSource>,
+        visited: &mut FxHashSet<Local>,
+    ) {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                self.place(place, proj, sources, visited)
+            }
+            Operand::Constant(constant) => match static_item(&constant.const_) {
+                Some(item) => {
+                    let name = name(item, self.tcx);
+                    sources.insert(ArgSource::Static { name });
+                }
+                None => _ = sources.insert(ArgSource::Const),
+            },
+        }
+    }
+
+    fn place(
+        &self,
+        place: &Place,
+        proj: &[ProjectionElem],
+        sources: &mut FxIndexSet<ArgSource>,
+        visited: &mut FxHashSet<Local>,
+    ) {
+        let local = place.local;
+        let proj = [&place.projection[..], proj].concat();
+        if (1..=self.body.arg_locals().len()).contains(&local) {
+            let place = self.place_str(local, &proj);
+            sources.insert(ArgSource::Arg {
+                index: local - 1,
+                place,
+            });
+            return;
+        }
+        if !visited.insert(local) {
+            return;
+        }
+
+        for def in self.defs.get(&local).into_iter().flatten() {
+            let rvalue = match def {
+                Def::Assign(rvalue) => rvalue,
+                Def::Call(callee) => {
+                    sources.insert(ArgSource::Ret {
+                        callee: callee.clone(),
+                    });
+                    continue;
+                }
+            };
+            match rvalue {
+                Rvalue::Use(op) | Rvalue::Cast(_, op, _) => {
+                    self.operand(op, &proj, sources, visited)
+                }
+                Rvalue::CopyForDeref(p) => self.place(p, &proj, sources, visited),
+                // A reference to the place, so dereferencing it reaches the place itself.
+                Rvalue::Ref(_, _, p) | Rvalue::AddressOf(_, p) => {
+                    let rest = match proj.split_first() {
+                        Some((ProjectionElem::Deref, rest)) => rest,
+                        _ => &[],
+                    };
+                    self.place(p, rest, sources, visited)
+                }
+                Rvalue::Aggregate(_, ops) => match proj.split_first() {
+                    Some((ProjectionElem::Field(idx, _), rest)) if *idx < ops.len() => {
+                        self.operand(&ops[*idx], rest, sources, visited)
+                    }
+                    _ => ops
+                        .iter()
+                        .for_each(|op| self.operand(op, &[], sources, visited)),
+                },
+                Rvalue::BinaryOp(_, a, b) | Rvalue::CheckedBinaryOp(_, a, b) => {
+                    self.operand(a, &[], sources, visited);
+                    self.operand(b, &[], sources, visited);
+                }
+                Rvalue::UnaryOp(_, op) | Rvalue::Repeat(op, _) | Rvalue::ShallowInitBox(op, _) => {
+                    self.operand(op, &[], sources, visited)
+                }
+                Rvalue::Len(p) | Rvalue::Discriminant(p) => self.place(p, &[], sources, visited),
+                Rvalue::ThreadLocalRef(_) | Rvalue::NullaryOp(_) => (),
+            }
+        }
+    }
+
+    /// Render the place with field names, like `(*self).len`.
+    fn place_str(&self, local: Local, proj: &[ProjectionElem]) -> String {
+        let body = self.body;
+        let mut s = body
+            .var_debug_info
+            .iter()
+            .find(|info| info.local() == Some(local))
+            .map(|info| info.name.clone())
+            .unwrap_or_else(|| format!("_{local}"));
+        let mut ty = body.locals()[local].ty;
+        let mut variant = None;
+        for elem in proj {
+            match elem {
+                ProjectionElem::Deref => s = format!("(*{s})"),
+                ProjectionElem::Field(idx, _) => {
+                    s = format!("{s}.{}", field_name(ty, variant.take(), *idx))
+                }
+                ProjectionElem::Index(_) => s.push_str("[_]"),
+                ProjectionElem::ConstantIndex {
+                    offset, from_end, ..
+                } => {
+                    let minus = if *from_end { "-" } else { "" };
+                    s = format!("{s}[{minus}{offset}]");
+                }
+                ProjectionElem::Subslice { from, to, from_end } => {
+                    let minus = if *from_end { "-" } else { "" };
+                    s = format!("{s}[{from}..{minus}{to}]");
+                }
+                ProjectionElem::Downcast(idx) => variant = Some(*idx),
+                ProjectionElem::OpaqueCast(_) => (),
+            }
+            match elem.ty(ty) {
+                Ok(elem_ty) => ty = elem_ty,
+                Err(_) => break,
+            }
+        }
+        if let Some(ProjectionElem::Deref) = proj.last()
+            && let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')'))
+        {
+            s = inner.to_owned();
+        }
+        s
+    }
+}
+
+/// The field name of an adt, or the index for tuples and closures.
+fn field_name(ty: Ty, variant: Option<VariantIdx>, idx: usize) -> String {
+    if let TyKind::RigidTy(RigidTy::Adt(adt_def, _)) = ty.kind()
+        && let Some(variant) = match variant {
+            Some(idx) => adt_def.variant(idx),
+            None => adt_def.variants().into_iter().next(),
+        }
+        && let Some(field) = variant.fields().into_iter().nth(idx)
+    {
+        field.name
+    } else {
+        idx.to_string()
+    }
+}
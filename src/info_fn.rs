@@ -1,13 +1,23 @@
 use crate::adt::{Adt, AdtAccess, CacheAdt, LocalsAccess, VaraintFieldIdx, new_adt};
 use crate::analyze_fn_def::Collector;
-use crate::output::utils::name;
-use crate::utils::{FxIndexMap, SmallVec, ThinVec};
+use crate::info_adt::FieldAccessKind;
+use crate::info_call::{CallSite, call_sites};
+use crate::output::utils::{did, name};
+use crate::utils::{FxIndexMap, FxIndexSet, SmallVec, ThinVec};
+use rustc_hir::def_id::DefId;
 use rustc_middle::ty::TyCtxt;
 use rustc_public::{
-    mir::{Body, Mutability, ProjectionElem},
-    ty::{FnDef, GenericArgKind, RigidTy, Ty, TyKind},
+    CrateItem,
+    mir::{
+        Body, LocalDecl, Mutability, Place, ProjectionElem,
+        mono::{Instance, InstanceKind},
+    },
+    rustc_internal::{internal, stable},
+    ty::{AdtDef, FnDef, GenericArgKind, GenericArgs, RigidTy, Span, Ty, TyKind},
 };
+use rustc_span::sym;
 use safety_parser::safety::PropertiesAndReason;
+use serde::Serialize;
 
 pub struct FnInfo {
     /// The owned return type.
@@ -21,26 +31,77 @@ pub struct FnInfo {
     /// The number of arguments this function takes.
     pub arg_count: usize,
     /// All types and places mentioned in the function.
-    #[expect(unused)]
     pub collector: Collector,
-    #[expect(unused)]
     pub v_sp: ThinVec<PropertiesAndReason>,
-    /// Direct callees in the function. The order is decided by MirVisitor,
-    /// and called functions is monomorphized.
+    /// Direct callees in the function. The order is decided by MirVisitor.
+    /// The key is the generic FnDef, and concrete generic arguments are
+    /// collected in [`CalleeInfo::instances`].
     pub callees: FxIndexMap<FnDef, CalleeInfo>,
     /// Direct adt places in the function. The adt is monomorphized.
     pub adts: FxIndexMap<Adt, LocalsAccess>,
+    /// Statics and consts accessed in the function. If accessed in several ways,
+    /// the privileged kind is kept.
+    pub statics: FxIndexMap<CrateItem, FieldAccessKind>,
+    /// Adt fields written through raw pointers like `(*ptr).field = value`, as the adt and the
+    /// field name, to spans of the writes.
+    pub raw_field_writes: FxIndexMap<(AdtDef, String), ThinVec<Span>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct CalleeInfo {
     pub non_instance_name: String,
+    /// Generic arguments observed for explicit uses of the callee, e.g. `[u8]` and `[Page]`
+    /// for `Vec::<T>::set_len`. Non-generic callees have one empty args. This is empty if
+    /// the callee is only invoked implicitly.
+    pub instances: FxIndexSet<GenericArgs>,
+    /// Set if the callee is invoked implicitly in the function. It may be called
+    /// explicitly as well, like `drop_in_place::<T>` for a value that is also dropped.
+    pub implicit: Option<Implicit>,
+    /// Generic arguments of implicit invocations.
+    pub implicit_instances: FxIndexSet<GenericArgs>,
+    /// Explicit calls to the callee in the function.
+    pub calls: ThinVec<CallSite>,
+}
+
+/// How a callee is invoked without a call expression.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Implicit {
+    /// `Drop::drop` or the drop glue of a dropped value.
+    Drop,
+}
+
+impl CalleeInfo {
+    fn new(fn_def: FnDef, tcx: TyCtxt) -> Self {
+        CalleeInfo {
+            // always encode the crate name
+            non_instance_name: name(fn_def, tcx),
+            instances: FxIndexSet::default(),
+            implicit: None,
+            implicit_instances: FxIndexSet::default(),
+            calls: ThinVec::new(),
+        }
+    }
+
+    /// The callee is explicitly used in the function, rather than only invoked implicitly.
+    pub fn is_explicit(&self) -> bool {
+        !self.instances.is_empty()
+    }
 }
 
 impl FnInfo {
+    /// The first span where the callee is mentioned, which is mostly the call.
+    pub fn callee_span(&self, callee: FnDef) -> Option<Span> {
+        self.collector.v_ty.iter().find_map(|ty| match ty.ty {
+            RigidTy::FnDef(fn_def, _) if fn_def == callee => Some(ty.span),
+            _ => None,
+        })
+    }
+
     pub fn new(
         collector: Collector,
         body: &Body,
+        owner: rustc_public::DefId,
         v_sp: ThinVec<PropertiesAndReason>,
         cache: &mut CacheAdt,
         tcx: TyCtxt,
@@ -49,12 +110,28 @@ impl FnInfo {
         // eprintln!("Find all instance");
         for ty in &collector.v_ty {
             // eprintln!("  {ty:?}");
-            if let RigidTy::FnDef(fn_def, _) = ty.ty {
-                let callee_info = CalleeInfo {
-                    // always encode the crate name
-                    non_instance_name: name(fn_def, tcx),
-                };
-                callees.insert(fn_def, callee_info);
+            if let RigidTy::FnDef(fn_def, args) = &ty.ty {
+                let callee_info = callees
+                    .entry(*fn_def)
+                    .or_insert_with(|| CalleeInfo::new(*fn_def, tcx));
+                callee_info.instances.insert(args.clone());
+            }
+        }
+        for (fn_def, call) in call_sites(body, owner, tcx) {
+            if let Some(callee_info) = callees.get_mut(&fn_def) {
+                callee_info.calls.push(call);
+            }
+        }
+        for place in &collector.v_drop {
+            let Ok(ty) = place.place.ty(body.locals()) else {
+                continue;
+            };
+            if let Some((fn_def, args)) = drop_callee(ty, tcx) {
+                let callee_info = callees
+                    .entry(fn_def)
+                    .or_insert_with(|| CalleeInfo::new(fn_def, tcx));
+                callee_info.implicit = Some(Implicit::Drop);
+                callee_info.implicit_instances.insert(args);
             }
         }
 
@@ -78,6 +155,21 @@ impl FnInfo {
         let mut ret_adts = Default::default();
         flatten_adts(&body.ret_local().ty, &mut ret_adts, cache);
 
+        let mut statics = FxIndexMap::<CrateItem, FieldAccessKind>::default();
+        for access in &collector.v_static {
+            statics
+                .entry(access.item)
+                .and_modify(|kind| *kind = (*kind).min(access.kind))
+                .or_insert(access.kind);
+        }
+
+        let mut raw_field_writes = FxIndexMap::<(AdtDef, String), ThinVec<Span>>::default();
+        for place in &collector.v_write {
+            if let Some(field) = raw_field_write(&place.place, body.locals()) {
+                raw_field_writes.entry(field).or_default().push(place.span);
+            }
+        }
+
         FnInfo {
             ret_adts,
             arg_count: body.arg_locals().len(),
@@ -85,7 +177,122 @@ impl FnInfo {
             v_sp,
             callees,
             adts,
+            statics,
+            raw_field_writes,
+        }
+    }
+}
+
+/// The adt field written by the place if it's behind a raw pointer, e.g. `(*ptr).field`.
+fn raw_field_write(place: &Place, locals: &[LocalDecl]) -> Option<(AdtDef, String)> {
+    let mut ty = locals[place.local].ty;
+    let mut behind_raw_ptr = false;
+    let mut variant = None;
+    for elem in &place.projection {
+        match elem {
+            ProjectionElem::Deref => behind_raw_ptr = ty.kind().is_raw_ptr(),
+            ProjectionElem::Downcast(idx) => variant = Some(*idx),
+            ProjectionElem::Field(idx, _) if behind_raw_ptr => {
+                let TyKind::RigidTy(RigidTy::Adt(adt_def, _)) = ty.kind() else {
+                    return None;
+                };
+                let variant = match variant {
+                    Some(idx) => adt_def.variant(idx)?,
+                    None => adt_def.variants().into_iter().next()?,
+                };
+                let field = variant.fields().into_iter().nth(*idx)?;
+                return Some((adt_def, field.name));
+            }
+            _ => behind_raw_ptr = false,
         }
+        ty = elem.ty(ty).ok()?;
+    }
+    None
+}
+
+/// A terminal function in the graph: it has no body to analyze, like
+/// a declaration in an `extern` block or a compiler intrinsic.
+#[derive(Debug, Clone, Serialize)]
+pub struct LeafInfo {
+    pub category: LeafCategory,
+    /// The ABI without quotes, like `C` or `Rust`.
+    pub abi: String,
+    /// The symbol to link against. For FFI, this is from `#[link_name]` if given.
+    pub link_name: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeafCategory {
+    /// A foreign function declared in an `extern` block.
+    Ffi,
+    /// A compiler intrinsic, like `core::intrinsics::copy_nonoverlapping`.
+    Intrinsic,
+    /// A foreign function linked to an LLVM intrinsic via `#[link_name = "llvm.*"]`.
+    Llvm,
+}
+
+impl LeafInfo {
+    /// Returns None if the function is neither a foreign function nor an intrinsic.
+    pub fn new(fn_def: FnDef, tcx: TyCtxt) -> Option<LeafInfo> {
+        let did = did(fn_def, tcx);
+        let category = if tcx.intrinsic(did).is_some() {
+            LeafCategory::Intrinsic
+        } else if tcx.is_foreign_item(did) {
+            LeafCategory::Ffi
+        } else {
+            return None;
+        };
+
+        let link_name = tcx
+            .codegen_fn_attrs(did)
+            .symbol_name
+            .unwrap_or_else(|| tcx.item_name(did))
+            .to_string();
+        let category = match category {
+            LeafCategory::Ffi if link_name.starts_with("llvm.") => LeafCategory::Llvm,
+            _ => category,
+        };
+        let abi = tcx.fn_sig(did).skip_binder().abi().as_str().to_owned();
+
+        Some(LeafInfo {
+            category,
+            abi,
+            link_name,
+        })
+    }
+}
+
+/// The function called when a value of the type is dropped: `Drop::drop` if the
+/// type implements it, or the drop glue `drop_in_place::<T>` otherwise.
+fn drop_callee(ty: Ty, tcx: TyCtxt) -> Option<(FnDef, GenericArgs)> {
+    let lang_items = tcx.lang_items();
+
+    let drop_trait = lang_items.drop_trait()?;
+    let drop_fn = *tcx
+        .associated_item_def_ids(drop_trait)
+        .iter()
+        .find(|&&did| tcx.item_name(did) == sym::drop)?;
+    // `<ty as Drop>::drop` is resolved to the impl if the type implements Drop.
+    if let Some((drop_fn, args)) = fn_def_with_ty(drop_fn, ty, tcx)
+        && let Ok(instance) = Instance::resolve(drop_fn, &args)
+        && matches!(instance.kind, InstanceKind::Item)
+        && let TyKind::RigidTy(RigidTy::FnDef(fn_def, args)) = instance.ty().kind()
+    {
+        return Some((fn_def, args));
+    }
+
+    // Drop glue is a compiler generated shim without an item of its own, so it's represented
+    // by the unresolved `drop_in_place::<T>`.
+    fn_def_with_ty(lang_items.drop_in_place_fn()?, ty, tcx)
+}
+
+/// Instantiate a function whose only generic parameter is the given type.
+fn fn_def_with_ty(did: DefId, ty: Ty, tcx: TyCtxt) -> Option<(FnDef, GenericArgs)> {
+    let fn_ty = rustc_middle::ty::Ty::new_fn_def(tcx, did, [internal(tcx, ty)]);
+    match stable(fn_ty).kind() {
+        TyKind::RigidTy(RigidTy::FnDef(fn_def, args)) => Some((fn_def, args)),
+        _ => None,
     }
 }
 
@@ -102,8 +309,8 @@ fn push_adt(
     };
 
     match ty {
-        RigidTy::Adt(def, _) => {
-            let adt = new_adt(def, cache);
+        RigidTy::Adt(def, args) => {
+            let adt = new_adt(def, &args, cache);
             let local = adts.entry(adt).or_default();
             local.locals.push(idx);
             // FIXME: ProjectionElem::Downcast(VariantIdx) should also be handled.
@@ -118,10 +325,10 @@ fn push_adt(
             };
         }
         RigidTy::Ref(_, ref_ty, mutability) => {
-            let TyKind::RigidTy(RigidTy::Adt(def, _)) = ref_ty.kind() else {
+            let TyKind::RigidTy(RigidTy::Adt(def, args)) = ref_ty.kind() else {
                 return;
             };
-            let adt = new_adt(def, cache);
+            let adt = new_adt(def, &args, cache);
             let is_struct = adt.def.kind().is_struct();
             let local = adts.entry(adt).or_default();
             local.locals.push(idx);
@@ -165,7 +372,7 @@ fn flatten_adts(ty: &Ty, v: &mut SmallVec<[Adt; 1]>, cache: &mut CacheAdt) {
 
     match ty {
         RigidTy::Adt(def, args) => {
-            v.push(new_adt(def, cache));
+            v.push(new_adt(def, &args, cache));
             for arg in &args.0 {
                 if let GenericArgKind::Type(ty) = arg {
                     flatten_adts(ty, v, cache)
@@ -1,25 +1,97 @@
-use crate::FxIndexMap;
-use rustc_hir::{ImplItemImplKind, ImplItemKind, ItemId, ItemKind, OwnerNode, Ty, def_id::DefId};
+use crate::{
+    FxIndexMap,
+    check::unsafe_blocks,
+    diagnostics::{self, DiagnosticKind},
+    info_fn::{FnInfo, LeafInfo},
+    output::utils::{is_safe, stable_id, stable_id_internal, tag_names},
+    utils::FxHashMap,
+};
+use rustc_hir::{
+    self as hir, ForeignItemKind, HirId, Impl, ImplItemKind, Item, ItemKind, OwnerNode,
+    TraitItemKind, Ty,
+    def::DefKind,
+    def_id::{DefId, LocalDefId},
+};
 use rustc_middle::ty::{TyCtxt, TyKind};
-use rustc_span::{Ident, symbol::Symbol};
+use rustc_public::ty::FnDef;
+use rustc_span::symbol::Symbol;
 use serde::Serialize;
-use std::mem;
+use std::borrow::Cow;
 
 pub type ItemPath = Vec<DefPath>;
-pub type FlattenFreeItems = Vec<ItemPath>;
 
 pub fn navi(tcx: TyCtxt) -> Navigation {
     let free = free_items(tcx);
-    let tree = make_tree(&free.v_path, tcx);
+    let tree = make_tree(&free, tcx);
     Navigation::new(tree, &free)
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Serialize)]
 pub struct Tree {
     pub node: Node,
+    /// Counts of the subtree, which are filled after analysis.
+    #[serde(skip_serializing_if = "Stats::is_empty")]
+    pub stats: Stats,
     pub sub: Vec<Tree>,
 }
 
+/// Aggregated unsafety counts of items in a subtree.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Serialize)]
+pub struct Stats {
+    /// Functions, including FFI declarations.
+    pub fns: usize,
+    pub unsafe_fns: usize,
+    /// Safe functions calling unsafe functions.
+    pub safe_fns_with_unsafe_callees: usize,
+    pub unsafe_blocks: usize,
+    /// Unsafe functions with safety tags.
+    pub tagged_unsafe_fns: usize,
+    pub untagged_unsafe_fns: usize,
+    /// ADTs with a raw pointer in field types.
+    pub adts_with_raw_ptr_fields: usize,
+}
+
+impl Stats {
+    fn is_empty(&self) -> bool {
+        *self == Stats::default()
+    }
+
+    fn add(&mut self, other: &Stats) {
+        self.fns += other.fns;
+        self.unsafe_fns += other.unsafe_fns;
+        self.safe_fns_with_unsafe_callees += other.safe_fns_with_unsafe_callees;
+        self.unsafe_blocks += other.unsafe_blocks;
+        self.tagged_unsafe_fns += other.tagged_unsafe_fns;
+        self.untagged_unsafe_fns += other.untagged_unsafe_fns;
+        self.adts_with_raw_ptr_fields += other.adts_with_raw_ptr_fields;
+    }
+
+    fn fn_(fn_def: FnDef, callees: Option<&FnInfo>, tcx: TyCtxt) -> Self {
+        let mut stats = Stats {
+            fns: 1,
+            unsafe_blocks: unsafe_blocks(fn_def, tcx).len(),
+            ..Default::default()
+        };
+        if !is_safe(fn_def) {
+            stats.unsafe_fns = 1;
+            if tag_names(fn_def).is_empty() {
+                stats.untagged_unsafe_fns = 1;
+            } else {
+                stats.tagged_unsafe_fns = 1;
+            }
+        } else if let Some(info) = callees
+            && info
+                .callees
+                .iter()
+                .any(|(&f, callee)| callee.is_explicit() && !is_safe(f))
+        {
+            // Implicit drops are not unsafe calls.
+            stats.safe_fns_with_unsafe_callees = 1;
+        }
+        stats
+    }
+}
+
 impl Tree {
     fn new(def_path: DefPath) -> Self {
         Tree {
@@ -27,17 +99,23 @@ impl Tree {
             node: Node {
                 inner: def_path,
                 id: 0,
+                stable_id: None,
             },
+            stats: Stats::default(),
             sub: Vec::new(),
         }
     }
 
-    fn push_direct_sub(&mut self, def_path: DefPath) -> &mut Self {
-        if let Some(pos) = self
-            .sub
-            .iter_mut()
-            .position(|sub| sub.node.inner == def_path)
-        {
+    /// The direct subtree of the def path. Given the stable id, a subtree of another item is
+    /// not reused, so items sharing a path have their own nodes.
+    fn push_direct_sub(&mut self, def_path: DefPath, stable_id: Option<&str>) -> &mut Self {
+        if let Some(pos) = self.sub.iter_mut().position(|sub| {
+            let node = &sub.node;
+            node.inner == def_path
+                && (stable_id.is_none()
+                    || node.stable_id.is_none()
+                    || node.stable_id.as_deref() == stable_id)
+        }) {
             // Move the node to last.
             let last_idx = self.sub.len() - 1;
             self.sub.swap(pos, last_idx);
@@ -48,13 +126,19 @@ impl Tree {
         self.sub.last_mut().unwrap()
     }
 
-    fn push(&mut self, v_path: &ItemPath) {
+    /// Push the path of the item, and set the stable id to the node of the item.
+    fn push(&mut self, v_path: &ItemPath, stable_id: &str) {
+        let Some((item, parents)) = v_path.split_last() else {
+            return;
+        };
         let mut tree = self;
-        for def_path in v_path {
+        for def_path in parents {
             if *def_path != tree.node.inner {
-                tree = tree.push_direct_sub(def_path.clone());
+                tree = tree.push_direct_sub(def_path.clone(), None);
             }
         }
+        let node = &mut tree.push_direct_sub(item.clone(), Some(stable_id)).node;
+        node.stable_id = Some(stable_id.to_owned());
     }
 
     fn sort(&mut self) {
@@ -73,19 +157,31 @@ impl Tree {
         }
     }
 
-    fn find_idx(&self, v_path: &ItemPath, buf: &mut Vec<usize>, id: &mut usize) {
+    /// Sum up stats of items in the subtree.
+    fn fill_stats(&mut self, stats: &mut FxHashMap<usize, Stats>) {
+        self.stats = stats.remove(&self.node.id).unwrap_or_default();
+        for subtree in &mut self.sub {
+            subtree.fill_stats(stats);
+            self.stats.add(&subtree.stats);
+        }
+    }
+
+    /// The id of the item node at the path, or None if it's not in the tree.
+    fn find_idx(&self, v_path: &ItemPath, stable_id: &str, buf: &mut Vec<usize>) -> Option<usize> {
+        let (item, parents) = v_path.split_last()?;
         let mut tree = self;
-        for def_path in v_path {
+        for def_path in parents {
             if *def_path != tree.node.inner {
-                if let Some(pos) = tree.sub.iter().position(|t| *def_path == t.node.inner) {
-                    buf.push(pos);
-                    tree = &tree.sub[pos];
-                } else {
-                    panic!("{def_path:?} nost found in {tree:?}");
-                }
+                let pos = tree.sub.iter().position(|t| *def_path == t.node.inner)?;
+                buf.push(pos);
+                tree = &tree.sub[pos];
             }
         }
-        *id = tree.node.id;
+        let is_item =
+            |t: &Tree| *item == t.node.inner && t.node.stable_id.as_deref() == Some(stable_id);
+        let pos = tree.sub.iter().position(is_item)?;
+        buf.push(pos);
+        Some(tree.sub[pos].node.id)
     }
 }
 
@@ -94,35 +190,73 @@ pub struct Node {
     #[serde(flatten)]
     pub inner: DefPath,
     pub id: usize,
+    /// The stable id of the item, which is absent for modules and types in paths.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct Navigation {
     pub tree: Tree,
-    pub name_to_id: FxIndexMap<String, usize>,
+    /// Stable ids of items to their names and node ids. Items may share a name, but not a
+    /// stable id.
+    pub items: FxIndexMap<String, NaviItem>,
     // pub name_to_node: FxIndexMap<String, String>,
     // pub node_to_name: FxIndexMap<String, String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct NaviItem {
+    pub name: String,
+    /// The node id in the tree.
+    pub id: usize,
+}
+
 impl Navigation {
     fn new(mut tree: Tree, free: &FreeItems) -> Self {
-        // Sort the subtrees to have stable idx.
-        tree.sort();
-        // Depth first id.
-        tree.fill_id(&mut 0);
-
-        let n = free.name_to_path.len();
-        let mut name_to_id =
-            FxIndexMap::<String, usize>::with_capacity_and_hasher(n, Default::default());
-
+        let crate_root = tree.node.inner.clone();
+        let mut paths: Vec<_> = free
+            .items
+            .iter()
+            .map(|(stable_id, (name, v_path))| (stable_id, name, Cow::Borrowed(v_path)))
+            .collect();
         let mut buf = Vec::<usize>::new();
-        let mut id = 0;
-        for (fn_name, v_path) in &free.name_to_path {
-            tree.find_idx(v_path, &mut buf, &mut id);
-            name_to_id.insert(fn_name.clone(), id);
+        loop {
+            // Sort the subtrees to have stable idx.
+            tree.sort();
+            // Depth first id.
+            tree.fill_id(&mut 0);
+
+            // Items not found in the tree are put under `__phony`, and ids are refilled.
+            let mut not_found = false;
+            for (stable_id, name, v_path) in &mut paths {
+                buf.clear();
+                if tree.find_idx(v_path, stable_id, &mut buf).is_none() {
+                    let phony = put_under_phony(v_path.to_vec(), &crate_root);
+                    let message = format!("{v_path:?} is not found, and put under {phony:?}");
+                    diagnostics::report(DiagnosticKind::NodeNotFound, name.as_str(), message);
+                    tree.push(&phony, stable_id);
+                    *v_path = Cow::Owned(phony);
+                    not_found = true;
+                }
+            }
+            if !not_found {
+                break;
+            }
+        }
 
-            id = 0;
+        let n = paths.len();
+        let mut items =
+            FxIndexMap::<String, NaviItem>::with_capacity_and_hasher(n, Default::default());
+
+        for (stable_id, name, v_path) in &paths {
             buf.clear();
+            // All paths are in the tree now.
+            let Some(id) = tree.find_idx(v_path, stable_id, &mut buf) else {
+                continue;
+            };
+            let name = (*name).clone();
+            items.insert((*stable_id).clone(), NaviItem { name, id });
         }
 
         // let mut name_to_node =
@@ -144,11 +278,58 @@ impl Navigation {
         //     .collect();
         // node_to_name.sort_unstable_keys();
 
-        Navigation { tree, name_to_id }
+        Navigation { tree, items }
+    }
+
+    /// Fill stats of functions and ADTs into nodes, and sum them up in each subtree.
+    pub fn fill_stats(
+        &mut self,
+        map_fn: &FxIndexMap<FnDef, FnInfo>,
+        map_leaf: &FxIndexMap<FnDef, LeafInfo>,
+        tcx: TyCtxt,
+    ) {
+        let mut stats = FxHashMap::<usize, Stats>::default();
+        let mut add = |stable_id: &str, item: Stats| {
+            if let Some(id) = self.node_id(stable_id) {
+                stats.entry(id).or_default().add(&item);
+            }
+        };
+
+        for (&fn_def, info) in map_fn {
+            add(&stable_id(fn_def, tcx), Stats::fn_(fn_def, Some(info), tcx));
+        }
+        for &leaf in map_leaf.keys() {
+            add(&stable_id(leaf, tcx), Stats::fn_(leaf, None, tcx));
+        }
+
+        for item_id in tcx.hir_free_items() {
+            let did = item_id.owner_id.to_def_id();
+            if !matches!(
+                tcx.def_kind(did),
+                DefKind::Struct | DefKind::Enum | DefKind::Union
+            ) {
+                continue;
+            }
+            let has_raw_ptr = tcx.adt_def(did).all_fields().any(|field| {
+                let ty = tcx.type_of(field.did).skip_binder();
+                ty.walk()
+                    .any(|arg| arg.as_type().is_some_and(|ty| ty.is_raw_ptr()))
+            });
+            if has_raw_ptr {
+                let adt = Stats {
+                    adts_with_raw_ptr_fields: 1,
+                    ..Default::default()
+                };
+                add(&stable_id_internal(did, tcx), adt);
+            }
+        }
+
+        self.tree.fill_stats(&mut stats);
     }
 
-    pub fn name_to_id(&self, name: &str) -> Option<usize> {
-        self.name_to_id.get(name).copied()
+    /// The node id of the item with the stable id.
+    pub fn node_id(&self, stable_id: &str) -> Option<usize> {
+        self.items.get(stable_id).map(|item| item.id)
     }
 
     pub fn crate_root(&self) -> &str {
@@ -158,192 +339,324 @@ impl Navigation {
 
 #[derive(Default)]
 pub struct FreeItems {
-    v_path: FlattenFreeItems,
-    name_to_path: FxIndexMap<String, ItemPath>,
+    /// Stable id of the item to its name and path.
+    items: FxIndexMap<String, (String, ItemPath)>,
 }
 
-pub fn make_tree(v_path: &FlattenFreeItems, tcx: TyCtxt) -> Tree {
+pub fn make_tree(free: &FreeItems, tcx: TyCtxt) -> Tree {
     let crate_root = DefPath::crate_root(tcx);
     let mut tree = Tree {
         node: Node {
             inner: crate_root.clone(),
             id: 0,
+            stable_id: None,
         },
+        stats: Stats::default(),
         sub: Vec::new(),
     };
 
-    for free_item in v_path {
-        tree.push(free_item);
+    for (stable_id, (_, v_path)) in &free.items {
+        tree.push(v_path, stable_id);
     }
 
     tree
 }
 
 fn free_items(tcx: TyCtxt) -> FreeItems {
-    let mut this = FreeItems::default();
-    let FreeItems {
-        v_path,
-        name_to_path,
-    } = &mut this;
-    let crate_root = DefPath::crate_root(tcx);
+    let mut walker = Walker {
+        tcx,
+        crate_root: DefPath::crate_root(tcx),
+        paths: FxHashMap::default(),
+        free: FreeItems::default(),
+    };
 
     // Free items: those items may be inaccesible from user's perspective,
-    // and item paths are as per source code definitions.
+    // and item paths are as per source code definitions. Items nested in
+    // function bodies and generated by macros are free items as well.
     for item_id in tcx.hir_free_items() {
         let item = tcx.hir_item(item_id);
+        let did = item_id.owner_id.def_id;
         match &item.kind {
-            ItemKind::Fn { ident, .. } => {
-                push_plain_item_path(
-                    DefPathKind::Fn,
-                    ident,
-                    &item_id,
-                    tcx,
-                    v_path,
-                    &crate_root,
-                    name_to_path,
-                );
-            }
-            ItemKind::Struct(ident, ..) => {
-                push_plain_item_path(
-                    DefPathKind::Struct,
-                    ident,
-                    &item_id,
-                    tcx,
-                    v_path,
-                    &crate_root,
-                    name_to_path,
-                );
-            }
-            ItemKind::Enum(ident, ..) => {
-                push_plain_item_path(
-                    DefPathKind::Enum,
-                    ident,
-                    &item_id,
-                    tcx,
-                    v_path,
-                    &crate_root,
-                    name_to_path,
-                );
-            }
-            ItemKind::Union(ident, ..) => {
-                push_plain_item_path(
-                    DefPathKind::Union,
-                    ident,
-                    &item_id,
-                    tcx,
-                    v_path,
-                    &crate_root,
-                    name_to_path,
-                );
-            }
-            ItemKind::Trait(_, _, _, ident, ..) => {
-                push_plain_item_path(
-                    DefPathKind::TraitDecl,
-                    ident,
-                    &item_id,
-                    tcx,
-                    v_path,
-                    &crate_root,
-                    name_to_path,
-                );
+            ItemKind::Fn { .. }
+            | ItemKind::Struct(..)
+            | ItemKind::Enum(..)
+            | ItemKind::Union(..)
+            | ItemKind::Static(..)
+            | ItemKind::Const(..) => walker.push(did),
+            ItemKind::Trait(.., items) => {
+                walker.push(did);
+                // Required and provided methods, and assoc consts.
+                for id in *items {
+                    if let TraitItemKind::Fn(..) | TraitItemKind::Const(..) =
+                        tcx.hir_trait_item(*id).kind
+                    {
+                        walker.push(id.owner_id.def_id);
+                    }
+                }
             }
             ItemKind::Impl(imp) => {
+                // Unsafe impls are shown even if they have no assoc fn, like `unsafe impl Send`.
+                if let Some(header) = imp.of_trait
+                    && header.safety.is_unsafe()
+                    && let Some(trait_did) = header.trait_ref.trait_def_id()
+                {
+                    let impl_path = walker.self_ty_path(imp.self_ty);
+                    let impl_path = walker.impl_trait_path(impl_path, trait_did);
+                    walker.push_path(did, impl_path);
+                }
                 for id in imp.items {
-                    let assoc = tcx.hir_impl_item(*id);
-                    if let ImplItemKind::Fn(_, body) = assoc.kind {
-                        let mut impl_path = DefPath::from_ty(imp.self_ty, tcx, &crate_root);
-                        let fn_name = assoc.ident.as_str();
-                        match assoc.impl_kind {
-                            ImplItemImplKind::Inherent { .. } => {
-                                impl_path.push(DefPath::new(DefPathKind::AssocFn, fn_name));
-                            }
-                            ImplItemImplKind::Trait {
-                                trait_item_def_id, ..
-                            } => {
-                                if let Ok(did) = trait_item_def_id {
-                                    let trait_did =
-                                        tcx.opt_associated_item(did).unwrap().container_id(tcx);
-                                    let mut trait_path = def_path(trait_did, tcx);
-                                    if is_local_path(&impl_path, &crate_root) {
-                                        // The Self type is local, and put trait fn under Self.
-                                        // Repr: [local type path, trait path, assoc fn]
-                                        impl_path.extend(trait_path);
-                                    } else if is_local_path(&trait_path, &crate_root) {
-                                        // The trait is local, but Self is not, so put fn under trait.
-                                        // Repr [local trait path, external type path, assoc fn]
-                                        trait_path.extend(mem::take(&mut impl_path));
-                                        impl_path = trait_path;
-                                    } else {
-                                        // Neither Self or trait is not local. This is possible
-                                        // when Self is a fundamental type (see
-                                        // https://doc.rust-lang.org/reference/items/implementations.html#r-items.impl.trait.orphan-rule
-                                        // ), or coherence rules are relaxed to the whole project.
-                                        // As a workaround, we still have crate name as root, but
-                                        // set a phony submodule `__phony` before item path.
-                                        impl_path.extend(trait_path);
-                                        impl_path = put_under_phony(impl_path, &crate_root);
-                                    }
-                                    impl_path.push(DefPath::new(DefPathKind::AssocFn, fn_name));
-                                }
-                            }
-                        }
-                        v_path.push(impl_path.clone());
-                        let def_path_str = tcx.def_path_str(body.hir_id.owner);
-                        let def_path_str = format!("{}::{def_path_str}", crate_root.name);
-                        name_to_path.insert(def_path_str, impl_path);
+                    if let ImplItemKind::Fn(..) | ImplItemKind::Const(..) =
+                        tcx.hir_impl_item(*id).kind
+                    {
+                        walker.push(id.owner_id.def_id);
                     }
                 }
             }
+            ItemKind::ForeignMod { items, .. } => {
+                for id in *items {
+                    walker.push(id.owner_id.def_id);
+                }
+            }
             _ => (),
         }
     }
 
     // Put fully external root under __phony
-    let f = |v| normalize_root(v, &crate_root);
-    v_path.iter_mut().for_each(f);
-    name_to_path.values_mut().for_each(f);
+    let Walker {
+        crate_root,
+        free: mut this,
+        ..
+    } = walker;
+    for (name, path) in this.items.values_mut() {
+        if normalize_root(path, &crate_root) {
+            let message = format!("{name} is put under {path:?}");
+            diagnostics::report(DiagnosticKind::NonLocalRoot, name.as_str(), message);
+        }
+    }
 
     this
 }
 
-fn normalize_root(item_path: &mut ItemPath, crate_root: &DefPath) {
-    if item_path[0] != *crate_root {
-        eprintln!("{item_path:?} must start from {crate_root:?}");
-        // This is the escape hatch to not break the root principle.
-        *item_path = put_under_phony(item_path.clone(), crate_root)
-    }
+/// Computes item paths in the navigation tree.
+struct Walker<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    crate_root: DefPath,
+    /// Memoized item paths. None means the path is being computed or unavailable.
+    paths: FxHashMap<LocalDefId, Option<ItemPath>>,
+    free: FreeItems,
 }
 
-fn push_plain_item_path(
-    kind: DefPathKind,
-    ident: &Ident,
-    item_id: &ItemId,
-    tcx: TyCtxt,
-    v_path: &mut Vec<ItemPath>,
-    crate_root: &DefPath,
-    map_name_to_path: &mut FxIndexMap<String, ItemPath>,
-) {
-    let mut path = vec![DefPath::new(kind, ident.as_str())];
-    push_parent_paths(&mut path, item_id, tcx, crate_root);
-    v_path.push(path.clone());
-    let def_path_str = tcx.def_path_str(item_id.owner_id.to_def_id());
-    let def_path_str = format!("{}::{def_path_str}", crate_root.name);
-    map_name_to_path.insert(def_path_str, path);
-}
+impl Walker<'_> {
+    fn push(&mut self, did: LocalDefId) {
+        if let Some(path) = self.path_of(did) {
+            self.push_path(did, path);
+        }
+    }
+
+    fn push_path(&mut self, did: LocalDefId, path: ItemPath) {
+        let tcx = self.tcx;
+        let did = did.to_def_id();
+        let def_path_str = format!("{}::{}", self.crate_root.name, tcx.def_path_str(did));
+        let stable_id = stable_id_internal(did, tcx);
+        self.free.items.insert(stable_id, (def_path_str, path));
+    }
+
+    fn path_of(&mut self, did: LocalDefId) -> Option<ItemPath> {
+        if let Some(path) = self.paths.get(&did) {
+            return path.clone();
+        }
+        // Guard against cycles.
+        self.paths.insert(did, None);
+        let path = self.compute_path(did);
+        self.paths.insert(did, path.clone());
+        path
+    }
 
-fn push_parent_paths(path: &mut Vec<DefPath>, item_id: &ItemId, tcx: TyCtxt, crate_root: &DefPath) {
-    for (_, owner_node) in tcx.hir_parent_owner_iter(item_id.hir_id()) {
-        match owner_node {
-            OwnerNode::Item(owner_item) => {
-                if let ItemKind::Mod(mod_ident, _) = owner_item.kind {
-                    path.push(DefPath::new(DefPathKind::Mod, mod_ident.as_str()));
+    fn compute_path(&mut self, did: LocalDefId) -> Option<ItemPath> {
+        let tcx = self.tcx;
+        let (mut path, kind, name) = match tcx.hir_node_by_def_id(did) {
+            hir::Node::Item(item) => {
+                let kind = match item.kind {
+                    ItemKind::Fn { .. } => DefPathKind::Fn,
+                    ItemKind::Struct(..) => DefPathKind::Struct,
+                    ItemKind::Enum(..) => DefPathKind::Enum,
+                    ItemKind::Union(..) => DefPathKind::Union,
+                    ItemKind::Static(..) => DefPathKind::Static,
+                    ItemKind::Const(..) => DefPathKind::Const,
+                    ItemKind::Trait(..) => DefPathKind::TraitDecl,
+                    _ => return None,
+                };
+                (self.parent_path(item.hir_id()), kind, item.kind.ident()?)
+            }
+            hir::Node::TraitItem(assoc) => {
+                let kind = match assoc.kind {
+                    TraitItemKind::Fn(..) => DefPathKind::AssocFn,
+                    TraitItemKind::Const(..) => DefPathKind::Const,
+                    TraitItemKind::Type(..) => return None,
+                };
+                let trait_did = tcx.local_parent(did);
+                (self.path_of(trait_did)?, kind, assoc.ident)
+            }
+            hir::Node::ImplItem(assoc) => {
+                let kind = match assoc.kind {
+                    ImplItemKind::Fn(..) => DefPathKind::AssocFn,
+                    ImplItemKind::Const(..) => DefPathKind::Const,
+                    ImplItemKind::Type(..) => return None,
+                };
+                let hir::Node::Item(Item {
+                    kind: ItemKind::Impl(imp),
+                    ..
+                }) = tcx.hir_node_by_def_id(tcx.local_parent(did))
+                else {
+                    return None;
+                };
+                (self.impl_path(imp), kind, assoc.ident)
+            }
+            hir::Node::ForeignItem(item) => {
+                let kind = match item.kind {
+                    ForeignItemKind::Fn(..) => DefPathKind::Fn,
+                    ForeignItemKind::Static(..) => DefPathKind::Static,
+                    ForeignItemKind::Type => return None,
+                };
+                (self.parent_path(item.hir_id()), kind, item.ident)
+            }
+            _ => return None,
+        };
+        path.push(DefPath::new(kind, name.as_str()));
+        Some(path)
+    }
+
+    /// Modules up to the crate root, or to the nearest item with a body like a function,
+    /// under which nested items are put.
+    fn parent_path(&mut self, hir_id: HirId) -> ItemPath {
+        let tcx = self.tcx;
+        let mut mods = Vec::new();
+        let mut base = None;
+        for (owner_id, owner_node) in tcx.hir_parent_owner_iter(hir_id) {
+            match owner_node {
+                OwnerNode::Item(owner_item) => match owner_item.kind {
+                    ItemKind::Mod(mod_ident, _) => {
+                        mods.push(DefPath::new(DefPathKind::Mod, mod_ident.as_str()));
+                    }
+                    ItemKind::Fn { .. } | ItemKind::Static(..) | ItemKind::Const(..) => {
+                        base = self.path_of(owner_id.def_id);
+                        break;
+                    }
+                    _ => (),
+                },
+                OwnerNode::ImplItem(_) | OwnerNode::TraitItem(_) => {
+                    base = self.path_of(owner_id.def_id);
+                    break;
                 }
+                _ => (),
             }
-            OwnerNode::Crate(_) => path.push(crate_root.clone()),
-            _ => (),
         }
+        let mut path = base.unwrap_or_else(|| vec![self.crate_root.clone()]);
+        path.extend(mods.into_iter().rev());
+        path
     }
-    path.reverse();
+
+    /// Path to the Self type of the impl, with the trait path if it's a trait impl.
+    fn impl_path(&mut self, imp: &Impl) -> ItemPath {
+        let path = self.self_ty_path(imp.self_ty);
+        match imp
+            .of_trait
+            .and_then(|header| header.trait_ref.trait_def_id())
+        {
+            Some(trait_did) => self.impl_trait_path(path, trait_did),
+            None => path,
+        }
+    }
+
+    fn self_ty_path(&mut self, ty: &Ty) -> ItemPath {
+        let tcx = self.tcx;
+        // Convert hir Ty to middle Ty.
+        let typ = tcx.type_of(ty.hir_id.owner).skip_binder();
+        // A type wrapping adts like `&Adt` or `(Adt, u8)` is put under the first local adt in
+        // it, or the first adt if none is local, e.g. `&Option<Adt>` is put under `Adt`.
+        let adts: Vec<_> = typ
+            .walk()
+            .filter_map(|arg| match arg.as_type()?.kind() {
+                TyKind::Adt(def, _) => Some(def.did()),
+                _ => None,
+            })
+            .collect();
+        let adt = adts
+            .iter()
+            .find(|did| did.is_local())
+            .or(adts.first())
+            .copied();
+        let kind = match typ.kind() {
+            TyKind::Adt(def, _) if Some(def.did()) == adt => return self.def_path(def.did()),
+            // A foreign adt wrapping a local one like `Option<Adt>` has its own node.
+            TyKind::Adt(..) => DefPathKind::Ty,
+            TyKind::Ref(..) => DefPathKind::Ref,
+            TyKind::RawPtr(..) => DefPathKind::Ptr,
+            TyKind::Slice(_) => DefPathKind::Slice,
+            TyKind::Tuple(_) => DefPathKind::Tuple,
+            TyKind::Array(..) => DefPathKind::Array,
+            TyKind::Param(_) => DefPathKind::Param,
+            _ => DefPathKind::Ty,
+        };
+        let mut path = match adt {
+            Some(did) => self.def_path(did),
+            // cc https://github.com/os-checker/unsafety-propagation-graph/issues/15
+            None => vec![self.crate_root.clone(), DefPath::primitive()],
+        };
+        path.push(DefPath::new(kind, typ.to_string()));
+        path
+    }
+
+    /// Local items are in the same path as they're pushed, and traits are `ImplTrait`.
+    fn def_path(&mut self, did: DefId) -> ItemPath {
+        if let Some(local) = did.as_local()
+            && let Some(mut path) = self.path_of(local)
+        {
+            if let Some(last) = path.last_mut()
+                && last.kind == DefPathKind::TraitDecl
+            {
+                last.kind = DefPathKind::ImplTrait;
+            }
+            return path;
+        }
+        def_path(did, self.tcx)
+    }
+
+    /// Path to a trait impl without assoc items.
+    fn impl_trait_path(&mut self, mut impl_path: ItemPath, trait_did: DefId) -> ItemPath {
+        let crate_root = &self.crate_root.clone();
+        let mut trait_path = self.def_path(trait_did);
+        if is_local_path(&impl_path, crate_root) {
+            // The Self type is local, and put trait fn under Self.
+            // Repr: [local type path, trait path, assoc fn]
+            impl_path.extend(trait_path);
+            impl_path
+        } else if is_local_path(&trait_path, crate_root) {
+            // The trait is local, but Self is not, so put fn under trait.
+            // Repr [local trait path, external type path, assoc fn]
+            trait_path.extend(impl_path);
+            trait_path
+        } else {
+            // Neither Self or trait is not local. This is possible
+            // when Self is a fundamental type (see
+            // https://doc.rust-lang.org/reference/items/implementations.html#r-items.impl.trait.orphan-rule
+            // ), or coherence rules are relaxed to the whole project.
+            // As a workaround, we still have crate name as root, but
+            // set a phony submodule `__phony` before item path.
+            impl_path.extend(trait_path);
+            put_under_phony(impl_path, crate_root)
+        }
+    }
+}
+
+/// Returns true if the path doesn't start from the crate root, and is put under `__phony`.
+fn normalize_root(item_path: &mut ItemPath, crate_root: &DefPath) -> bool {
+    let non_local = !is_local_path(item_path, crate_root);
+    if non_local {
+        // This is the escape hatch to not break the root principle.
+        *item_path = put_under_phony(item_path.clone(), crate_root)
+    }
+    non_local
 }
 
 #[derive(Clone, Debug, Serialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -360,25 +673,6 @@ impl DefPath {
         }
     }
 
-    pub fn from_ty(ty: &Ty, tcx: TyCtxt, crate_root: &DefPath) -> Vec<Self> {
-        let hir_id = ty.hir_id;
-        // Convert hir Ty to middle Ty.
-        let typ = tcx.type_of(hir_id.owner).skip_binder();
-        // let typ = tcx
-        //     .try_normalize_erasing_regions(TypingEnv::fully_monomorphized(), typ)
-        //     .unwrap_or(typ);
-        if let TyKind::Adt(def, _) = typ.kind() {
-            def_path(def.did(), tcx)
-        } else {
-            // cc https://github.com/os-checker/unsafety-propagation-graph/issues/15
-            vec![
-                crate_root.clone(),
-                Self::primitive(),
-                Self::new(DefPathKind::Ty, typ.to_string()),
-            ]
-        }
-    }
-
     fn crate_root(tcx: TyCtxt) -> Self {
         DefPath::new(DefPathKind::Mod, crate_name(tcx).as_str())
     }
@@ -412,11 +706,7 @@ fn put_under_phony(mut v: ItemPath, crate_root: &DefPath) -> ItemPath {
 }
 
 fn is_local_path(v: &[DefPath], crate_root: &DefPath) -> bool {
-    if v.is_empty() {
-        unimplemented!()
-    } else {
-        v[0] == *crate_root
-    }
+    v.first() == Some(crate_root)
 }
 
 /// ADT path can be `[Mod, Adt]` where Adt is one of Struct, Enum, and Union.
@@ -426,9 +716,13 @@ fn is_local_path(v: &[DefPath], crate_root: &DefPath) -> bool {
 /// * `[Mod, Struct, AssocFn]` for an inherent function.
 /// * `[Mod, Struct, ImplTrait, AssocFn]` for a trait function.
 /// * `[Mod, TraitDecl, AssocFn]` for a trait function definition.
-/// * `[SelfTy, AssocFn]` for an unusual associated function like `impl &Adt`.
-/// * `[Mod, ImplTrait, SelfTy, AssocFn]` for an unusual trait function like `impl Trait for &Adt`,
-///   `impl Trait for (Adt1, Adt2)`, `impl<T> Trait for T`, or even `impl<T: Trait> Trait for T::U`.
+/// * `[Mod, Struct, SelfTy, ImplTrait, AssocFn]` for a trait function on a type wrapping an adt,
+///   like `impl Trait for &Adt` or `impl Trait for (Adt1, Adt2)`.
+/// * `[Mod, __primitive, SelfTy, ImplTrait, AssocFn]` for a trait function on a type without
+///   adts, like `impl Trait for &[u8]`, `impl<T> Trait for *mut T`, or `impl<T> Trait for T`.
+///
+/// SelfTy is one of Ref, Ptr, Slice, Tuple, Array, Param, and Ty for other types, named by the
+/// whole type.
 #[derive(Clone, Copy, Default, Debug, Serialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum DefPathKind {
     #[default]
@@ -441,10 +735,21 @@ pub enum DefPathKind {
     TraitDecl,
     Ty,
     ImplTrait,
+    Static,
+    Const,
+    /// `&T` or `&mut T`.
+    Ref,
+    /// `*const T` or `*mut T`.
+    Ptr,
+    Slice,
+    Tuple,
+    Array,
+    /// A generic type parameter.
+    Param,
 }
 
 fn def_path(did: DefId, tcx: TyCtxt) -> Vec<DefPath> {
-    use rustc_hir::{def::DefKind, definitions::DefPathData};
+    use rustc_hir::definitions::DefPathData;
 
     let default = || vec![DefPath::new(DefPathKind::Ty, tcx.def_path_str(did))];
 
@@ -479,3 +784,40 @@ fn def_path(did: DefId, tcx: TyCtxt) -> Vec<DefPath> {
 pub fn crate_name(tcx: TyCtxt) -> Symbol {
     tcx.crate_name(rustc_span::def_id::CrateNum::ZERO)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paths pushed to the tree are always found, so a path missing from the tree is only
+    /// possible with a tree built from other paths.
+    #[test]
+    fn node_not_found() {
+        let root = DefPath::new(DefPathKind::Mod, "krate");
+        let found = vec![root.clone(), DefPath::new(DefPathKind::Fn, "found")];
+        let missing = vec![root.clone(), DefPath::new(DefPathKind::Fn, "missing")];
+
+        let mut tree = Tree::new(root.clone());
+        tree.push(&found, "id_found");
+        let mut free = FreeItems::default();
+        for (id, name, path) in [
+            ("id_found", "krate::found", &found),
+            ("id_missing", "krate::missing", &missing),
+        ] {
+            free.items
+                .insert(id.to_owned(), (name.to_owned(), path.clone()));
+        }
+
+        let navi = Navigation::new(tree, &free);
+        let diagnostics = diagnostics::take();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert!(matches!(diagnostics[0].kind, DiagnosticKind::NodeNotFound));
+        assert_eq!(diagnostics[0].item, "krate::missing");
+
+        let mut buf = Vec::new();
+        let phony = put_under_phony(missing, &root);
+        let id = navi.tree.find_idx(&phony, "id_missing", &mut buf).unwrap();
+        assert_eq!(navi.node_id("id_missing"), Some(id));
+        assert!(navi.node_id("id_found").is_some());
+    }
+}
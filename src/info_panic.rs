@@ -0,0 +1,304 @@
+//! Paths on which a panic may occur after an invariant is possibly broken: a write to an
+//! invariant-bearing field or a call to an unsafe function, followed by a call that may
+//! panic, a drop that may unwind, or an assertion before the function returns or the field
+//! is written again.
+
+use crate::{
+    adt::{Adt, VaraintFieldIdx},
+    info_adt::AdtInfo,
+    info_call::assert_msg,
+    info_fn::FnInfo,
+    output::utils::{did, is_safe, name},
+    utils::{FxHashSet, FxIndexMap},
+};
+use rustc_hir::def::DefKind;
+use rustc_middle::{
+    middle::codegen_fn_attrs::CodegenFnAttrFlags,
+    ty::{TyCtxt, layout::fn_can_unwind},
+};
+use rustc_public::{
+    mir::{
+        BasicBlockIdx, Body, Place, ProjectionElem, Statement, StatementKind, Terminator,
+        TerminatorKind, UnwindAction,
+    },
+    ty::{AdtDef, FnDef, RigidTy, Span, TyKind},
+};
+use rustc_public_bridge::IndexedVal;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+pub struct PanicPath {
+    /// Where the invariant is possibly broken.
+    pub change: PathPoint,
+    /// The first terminator that may panic after the change.
+    pub panic: PathPoint,
+}
+
+#[derive(Clone)]
+pub struct PathPoint {
+    pub kind: PathPointKind,
+    /// The field like `Adt.field`, the callee, or the assertion message.
+    pub target: String,
+    pub span: Span,
+    pub bb: BasicBlockIdx,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum PathPointKind {
+    FieldWrite,
+    UnsafeCall,
+    /// A call that may panic.
+    Call,
+    /// A drop that may unwind, which runs `Drop` impls.
+    Drop,
+    Assert,
+}
+
+/// Panic paths in each function. Functions without such paths are omitted.
+pub fn panic_paths(
+    map_fn: &FxIndexMap<FnDef, FnInfo>,
+    map_adt: &FxIndexMap<Adt, AdtInfo>,
+    tcx: TyCtxt,
+) -> FxIndexMap<FnDef, Vec<PanicPath>> {
+    let fields = invariant_fields(map_adt);
+    let mut map = FxIndexMap::default();
+    for &fn_def in map_fn.keys() {
+        let Some(body) = fn_def.body() else {
+            continue;
+        };
+        let paths = fn_panic_paths(&body, &fields, tcx);
+        if !paths.is_empty() {
+            map.insert(fn_def, paths);
+        }
+    }
+    map
+}
+
+type InvariantFields = FxHashSet<(AdtDef, VaraintFieldIdx)>;
+
+/// Fields accessed by unsafe functions, which are assumed to carry safety invariants.
+fn invariant_fields(map_adt: &FxIndexMap<Adt, AdtInfo>) -> InvariantFields {
+    let mut set = FxHashSet::default();
+    for (adt, info) in map_adt {
+        // Field access is only collected for structs, so the index is the field index.
+        for (idx, access) in info.fields.iter().enumerate() {
+            let mut v_fn = access.read.iter().chain(&access.write).chain(&access.other);
+            if v_fn.any(|&fn_def| !is_safe(fn_def)) {
+                set.insert((adt.def, VaraintFieldIdx::new_field(idx)));
+            }
+        }
+    }
+    set
+}
+
+fn fn_panic_paths(body: &Body, fields: &InvariantFields, tcx: TyCtxt) -> Vec<PanicPath> {
+    let mut paths = Vec::new();
+    for (bb, block) in body.blocks.iter().enumerate() {
+        for (idx, stmt) in block.statements.iter().enumerate() {
+            let Some(target) = written_field(stmt, body, fields, tcx) else {
+                continue;
+            };
+            // A later write to the same field restores the invariant.
+            let restores = |stmt: &Statement| {
+                written_field(stmt, body, fields, tcx).is_some_and(|field| field == target)
+            };
+            if block.statements[idx + 1..].iter().any(restores) {
+                continue;
+            }
+            let change = PathPoint {
+                kind: PathPointKind::FieldWrite,
+                target: target.clone(),
+                span: stmt.span,
+                bb,
+            };
+            // The terminator of the same block comes right after the write.
+            push_paths(change, [bb], Some(&restores), body, tcx, &mut paths);
+        }
+
+        if let TerminatorKind::Call { func, target, .. } = &block.terminator.kind
+            && let Ok(ty) = func.ty(body.locals())
+            && let TyKind::RigidTy(RigidTy::FnDef(fn_def, _)) = ty.kind()
+            && !is_safe(fn_def)
+        {
+            let change = PathPoint {
+                kind: PathPointKind::UnsafeCall,
+                target: name(fn_def, tcx),
+                span: block.terminator.span,
+                bb,
+            };
+            push_paths(change, *target, None, body, tcx, &mut paths);
+        }
+    }
+    paths
+}
+
+/// The invariant-bearing field written by the statement.
+fn written_field(
+    stmt: &Statement,
+    body: &Body,
+    fields: &InvariantFields,
+    tcx: TyCtxt,
+) -> Option<String> {
+    match &stmt.kind {
+        StatementKind::Assign(place, _) => invariant_field(place, body, fields, tcx),
+        _ => None,
+    }
+}
+
+/// The written field if the place projects an invariant-bearing field, named as `Adt.field`.
+fn invariant_field(
+    place: &Place,
+    body: &Body,
+    fields: &InvariantFields,
+    tcx: TyCtxt,
+) -> Option<String> {
+    let mut ty = body.locals()[place.local].ty;
+    let mut variant = None;
+    let mut found = None;
+    for elem in &place.projection {
+        match elem {
+            ProjectionElem::Downcast(idx) => variant = Some(*idx),
+            ProjectionElem::Field(idx, _) => {
+                if let TyKind::RigidTy(RigidTy::Adt(adt_def, _)) = ty.kind() {
+                    let vf_idx = match variant {
+                        Some(v) => VaraintFieldIdx::new_variant_field(v.to_index(), *idx),
+                        None => VaraintFieldIdx::new_field(*idx),
+                    };
+                    let variant = match variant {
+                        Some(v) => adt_def.variant(v),
+                        None => adt_def.variants().into_iter().next(),
+                    };
+                    if fields.contains(&(adt_def, vf_idx))
+                        && let Some(field) = variant.and_then(|v| v.fields().into_iter().nth(*idx))
+                    {
+                        found = Some(format!("{}.{}", name(adt_def, tcx), field.name));
+                    }
+                }
+                variant = None;
+            }
+            _ => variant = None,
+        }
+        ty = elem.ty(ty).ok()?;
+    }
+    found
+}
+
+/// Search forward from the blocks along normal edges, and stop at the first terminators that
+/// may panic on each path, at returns, or at blocks with statements that restore the
+/// invariant. Statements of the start blocks are checked by callers.
+fn push_paths(
+    change: PathPoint,
+    start: impl IntoIterator<Item = BasicBlockIdx>,
+    restores: Option<&dyn Fn(&Statement) -> bool>,
+    body: &Body,
+    tcx: TyCtxt,
+    paths: &mut Vec<PanicPath>,
+) {
+    let mut visited = FxHashSet::default();
+    let mut queue: VecDeque<_> = start.into_iter().map(|bb| (bb, true)).collect();
+    while let Some((bb, is_start)) = queue.pop_front() {
+        if !visited.insert(bb) {
+            continue;
+        }
+        let block = &body.blocks[bb];
+        if !is_start
+            && let Some(restores) = restores
+            && block.statements.iter().any(restores)
+        {
+            continue;
+        }
+        let term = &block.terminator;
+        if let Some((kind, target)) = may_panic(term, body, tcx) {
+            let panic = PathPoint {
+                kind,
+                target,
+                span: term.span,
+                bb,
+            };
+            paths.push(PanicPath {
+                change: change.clone(),
+                panic,
+            });
+            continue;
+        }
+        queue.extend(normal_successors(term).into_iter().map(|bb| (bb, false)));
+    }
+}
+
+/// Calls that may panic, drops that may unwind, and assertions.
+fn may_panic(term: &Terminator, body: &Body, tcx: TyCtxt) -> Option<(PathPointKind, String)> {
+    match &term.kind {
+        TerminatorKind::Call { func, .. } => {
+            let ty = func.ty(body.locals()).ok()?;
+            let callee = match ty.kind() {
+                TyKind::RigidTy(RigidTy::FnDef(fn_def, _)) if !callee_may_panic(fn_def, tcx) => {
+                    return None;
+                }
+                TyKind::RigidTy(RigidTy::FnDef(fn_def, _)) => name(fn_def, tcx),
+                // Function pointers and closures are unknown.
+                _ => format!("{ty}"),
+            };
+            Some((PathPointKind::Call, callee))
+        }
+        TerminatorKind::Drop { place, unwind, .. } if drop_may_unwind(unwind, tcx) => {
+            let ty = place.ty(body.locals()).ok()?;
+            Some((PathPointKind::Drop, format!("{ty}")))
+        }
+        TerminatorKind::Assert { msg, .. } => {
+            Some((PathPointKind::Assert, assert_msg(msg).to_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether the callee may panic, decided from the callee rather than the unwind action of
+/// the call, because no call unwinds with `-Cpanic=abort`.
+fn callee_may_panic(fn_def: FnDef, tcx: TyCtxt) -> bool {
+    // Intrinsics without `#[rustc_nounwind]` that never panic.
+    const NON_PANICKING_INTRINSICS: &[&str] = &["ub_checks", "overflow_checks"];
+
+    if let Some(intrinsic) = fn_def.as_intrinsic()
+        && NON_PANICKING_INTRINSICS.contains(&intrinsic.fn_name().as_str())
+    {
+        return false;
+    }
+    let did = did(fn_def, tcx);
+    let kind = tcx.def_kind(did);
+    if matches!(kind, DefKind::Ctor(..))
+        || kind.has_codegen_attrs()
+            && tcx
+                .codegen_fn_attrs(did)
+                .flags
+                .contains(CodegenFnAttrFlags::NEVER_UNWIND)
+    {
+        return false;
+    }
+    // Foreign functions with ABIs like `extern "C"` abort instead of unwinding.
+    if matches!(kind, DefKind::Fn | DefKind::AssocFn) {
+        let abi = tcx.fn_sig(did).skip_binder().abi();
+        return abi.is_rustic_abi() || fn_can_unwind(tcx, None, abi);
+    }
+    true
+}
+
+/// Drops without unwind edges can't unwind, except with `-Cpanic=abort`, where no drop has
+/// unwind edges but a `Drop` impl may still panic.
+fn drop_may_unwind(unwind: &UnwindAction, tcx: TyCtxt) -> bool {
+    !matches!(unwind, UnwindAction::Unreachable) || !tcx.sess.panic_strategy().unwinds()
+}
+
+/// Successors except unwind edges.
+fn normal_successors(term: &Terminator) -> Vec<BasicBlockIdx> {
+    match &term.kind {
+        TerminatorKind::Goto { target }
+        | TerminatorKind::Drop { target, .. }
+        | TerminatorKind::Assert { target, .. } => vec![*target],
+        TerminatorKind::Call { target, .. } => target.iter().copied().collect(),
+        TerminatorKind::InlineAsm { destination, .. } => destination.iter().copied().collect(),
+        TerminatorKind::SwitchInt { targets, .. } => targets.all_targets(),
+        TerminatorKind::Resume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable => Vec::new(),
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    adt::CacheAdt,
+    analyze_fn_def,
+    info_adt::{Access, FieldAccessKind},
+    info_fn::FnInfo,
+    output::utils::did,
+    utils::{FxIndexMap, ThinVec},
+};
+use rustc_hir::def::DefKind;
+use rustc_middle::ty::{TyCtxt, TypingEnv};
+use rustc_public::{CrateDef, CrateItem, ty::FnDef};
+use serde::Serialize;
+
+/// Statics and consts defined in the local crate, with functions accessing them.
+pub fn static_info(
+    map_fn: &FxIndexMap<FnDef, FnInfo>,
+    cache: &mut CacheAdt,
+    tcx: TyCtxt,
+) -> FxIndexMap<CrateItem, StaticInfo> {
+    let mut map_static = FxIndexMap::<CrateItem, StaticInfo>::default();
+
+    for item in rustc_public::all_local_items() {
+        let Some(kind) = StaticKind::new(item, tcx) else {
+            continue;
+        };
+        // Trait consts without default value have no body.
+        let Some(body) = item.body() else {
+            continue;
+        };
+        let collector = analyze_fn_def::collect(&body);
+        let init = FnInfo::new(collector, &body, item.def_id(), ThinVec::new(), cache, tcx);
+        map_static.insert(
+            item,
+            StaticInfo {
+                kind,
+                init,
+                access: Access::default(),
+            },
+        );
+    }
+
+    for (&fn_def, fn_info) in map_fn {
+        for (item, kind) in &fn_info.statics {
+            if let Some(info) = map_static.get_mut(item) {
+                let access = &mut info.access;
+                match kind {
+                    FieldAccessKind::Write => access.write.push(fn_def),
+                    FieldAccessKind::Read => access.read.push(fn_def),
+                    FieldAccessKind::Other => access.other.push(fn_def),
+                }
+            }
+        }
+    }
+
+    map_static
+}
+
+pub struct StaticInfo {
+    pub kind: StaticKind,
+    /// Analysis of the initializer, like a function body.
+    pub init: FnInfo,
+    /// Functions that read or write the item.
+    pub access: Access,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum StaticKind {
+    Static,
+    StaticMut,
+    /// An immutable static whose type is `!Freeze`, like `AtomicUsize` or `Mutex<T>`, so it can
+    /// still be written through shared references.
+    StaticInteriorMut,
+    Const,
+}
+
+impl StaticKind {
+    /// Returns None if the item is neither a static nor a const item.
+    fn new(item: CrateItem, tcx: TyCtxt) -> Option<StaticKind> {
+        let did = did(item, tcx);
+        match tcx.def_kind(did) {
+            // Nested statics are anonymous allocations inside a static.
+            DefKind::Static { nested: true, .. } => None,
+            DefKind::Static { mutability, .. } if mutability.is_mut() => {
+                Some(StaticKind::StaticMut)
+            }
+            DefKind::Static { .. } => {
+                let ty = tcx.type_of(did).instantiate_identity();
+                if ty.is_freeze(tcx, TypingEnv::post_analysis(tcx, did)) {
+                    Some(StaticKind::Static)
+                } else {
+                    Some(StaticKind::StaticInteriorMut)
+                }
+            }
+            DefKind::Const | DefKind::AssocConst => Some(StaticKind::Const),
+            _ => None,
+        }
+    }
+}
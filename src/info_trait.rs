@@ -0,0 +1,37 @@
+use crate::{output::utils::did, utils::FxIndexMap};
+use rustc_hir::Safety as ISafety;
+use rustc_middle::ty::TyCtxt;
+use rustc_public::{
+    mir::Safety,
+    ty::{ImplDef, TraitDef},
+};
+
+/// Unsafe traits defined in the local crate, and local unsafe impls.
+///
+/// A non-local unsafe trait like `Send` is included once it's implemented locally.
+pub fn trait_info(tcx: TyCtxt) -> FxIndexMap<TraitDef, TraitInfo> {
+    let local_crate = rustc_public::local_crate();
+    let mut map_trait = FxIndexMap::<TraitDef, TraitInfo>::default();
+
+    for trait_def in local_crate.trait_decls() {
+        if matches!(TraitDef::declaration(&trait_def).safety, Safety::Unsafe) {
+            map_trait.insert(trait_def, TraitInfo::default());
+        }
+    }
+
+    for impl_def in local_crate.trait_impls() {
+        let header = tcx.impl_trait_header(did(impl_def, tcx));
+        if matches!(header.safety, ISafety::Unsafe) {
+            let trait_def = impl_def.trait_impl().value.def_id;
+            map_trait.entry(trait_def).or_default().impls.push(impl_def);
+        }
+    }
+
+    map_trait
+}
+
+#[derive(Default)]
+pub struct TraitInfo {
+    /// Local unsafe impls of the trait.
+    pub impls: Vec<ImplDef>,
+}
@@ -0,0 +1,124 @@
+//! Lint mode: report findings as rustc lints at source spans when `UPG_LINT` is set.
+//!
+//! Lints are named under the registered `rapx` tool and registered in the lint store, so their
+//! levels are decided by rustc from attributes like `#[allow(rapx::missing_safety_tag)]` on the
+//! item or any parent of it, and from flags like `-D` and `--cap-lints`.
+
+use crate::{
+    FxIndexMap, FxIndexSet,
+    check::{has_safety_comment, unsafe_blocks},
+    info_fn::FnInfo,
+    output::utils::{is_safe, name, tag_names},
+};
+use rustc_hir::HirId;
+use rustc_lint::LintStore;
+use rustc_middle::{ty::TyCtxt, util::Providers};
+use rustc_public::{
+    CrateDef,
+    rustc_internal::internal,
+    ty::{FnDef, RigidTy},
+};
+use rustc_session::{Session, declare_tool_lint, lint::Lint};
+use rustc_span::{Span, Symbol};
+use std::sync::OnceLock;
+
+declare_tool_lint! {
+    /// An unsafe fn has no safety tags.
+    pub rapx::MISSING_SAFETY_TAG,
+    Warn,
+    "unsafe function without safety tags"
+}
+
+declare_tool_lint! {
+    /// A safe fn calls an unsafe fn without safety tags.
+    pub rapx::UNTAGGED_UNSAFE_CALLEE,
+    Warn,
+    "call to an unsafe function without safety tags"
+}
+
+declare_tool_lint! {
+    /// An unsafe block has no `// SAFETY:` comment.
+    pub rapx::UNDOCUMENTED_UNSAFE_BLOCK,
+    Warn,
+    "unsafe block without a `// SAFETY:` comment"
+}
+
+/// Register lints, so that their levels are controlled by attributes and flags like
+/// `-D rapx::missing_safety_tag` and `--cap-lints`.
+pub fn register(store: &mut LintStore) {
+    store.register_lints(&[
+        MISSING_SAFETY_TAG,
+        UNTAGGED_UNSAFE_CALLEE,
+        UNDOCUMENTED_UNSAFE_BLOCK,
+    ]);
+}
+
+/// The `check_expectations` query of rustc, deferred until lints of the driver are emitted.
+static CHECK_EXPECTATIONS: OnceLock<fn(TyCtxt<'_>, Option<Symbol>)> = OnceLock::new();
+
+/// Rustc checks `#[expect]` at the end of the analysis, which is before the driver runs, so
+/// expectations of the lints would never be fulfilled. Replace the check with a no-op, and
+/// run it in [`check_expectations`] instead.
+pub fn override_queries(_: &Session, providers: &mut Providers) {
+    _ = CHECK_EXPECTATIONS.set(providers.queries.check_expectations);
+    providers.queries.check_expectations = |_, _| {};
+}
+
+/// Report unfulfilled `#[expect]` of all lints. Call this after lints are emitted.
+pub fn check_expectations(tcx: TyCtxt) {
+    if let Some(check) = CHECK_EXPECTATIONS.get() {
+        check(tcx, None);
+    }
+}
+
+fn emit(lint: &'static Lint, hir_id: HirId, span: Span, msg: String, tcx: TyCtxt) {
+    tcx.node_span_lint(lint, hir_id, span, |diag| {
+        diag.primary_message(msg);
+    });
+}
+
+/// Emit lints if `UPG_LINT` is set.
+pub fn lint(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt) {
+    if !std::env::var("UPG_LINT").is_ok_and(|s| s != "0") {
+        return;
+    }
+    for (&fn_def, info) in map_fn {
+        let Some(local) = internal(tcx, fn_def.def_id()).as_local() else {
+            continue;
+        };
+        let hir_id = tcx.local_def_id_to_hir_id(local);
+        let fn_name = name(fn_def, tcx);
+
+        if !is_safe(fn_def) {
+            if info.v_sp.is_empty() {
+                let span = tcx.def_ident_span(local).unwrap_or(tcx.def_span(local));
+                let msg = format!("unsafe function `{fn_name}` has no safety tags");
+                emit(MISSING_SAFETY_TAG, hir_id, span, msg, tcx);
+            }
+        } else {
+            // The first use of each unsafe callee, which is mostly the call.
+            let mut reported = FxIndexSet::default();
+            for ty in &info.collector.v_ty {
+                if let RigidTy::FnDef(callee, _) = ty.ty
+                    && !is_safe(callee)
+                    && reported.insert(callee)
+                    && tag_names(callee).is_empty()
+                {
+                    let msg = format!(
+                        "`{fn_name}` calls unsafe function `{}` which has no safety tags",
+                        name(callee, tcx)
+                    );
+                    let span = internal(tcx, ty.span);
+                    emit(UNTAGGED_UNSAFE_CALLEE, hir_id, span, msg, tcx);
+                }
+            }
+        }
+
+        for (block_id, span) in unsafe_blocks(fn_def, tcx) {
+            if !has_safety_comment(block_id, span, tcx) {
+                let msg = "unsafe block has no `// SAFETY:` comment".to_owned();
+                emit(UNDOCUMENTED_UNSAFE_BLOCK, block_id, span, msg, tcx);
+            }
+        }
+    }
+}
@@ -7,20 +7,30 @@ extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_hir;
 extern crate rustc_interface;
+extern crate rustc_lint;
 extern crate rustc_middle;
 extern crate rustc_public;
 extern crate rustc_public_bridge;
+extern crate rustc_session;
 extern crate rustc_span;
 
+use rustc_driver::Compilation;
 use rustc_middle::ty::TyCtxt;
 use rustc_public::CrateDef;
 use std::ops::ControlFlow;
 
 mod adt;
 mod analyze_fn_def;
+mod check;
+mod diagnostics;
 mod info_adt;
+mod info_call;
 mod info_fn;
 mod info_mod;
+mod info_panic;
+mod info_static;
+mod info_trait;
+mod lint;
 mod output;
 
 mod utils;
@@ -28,38 +38,94 @@ pub use utils::{FxIndexMap, FxIndexSet, ThinVec};
 
 fn main() {
     let rustc_args: Vec<_> = std::env::args().collect();
-    _ = rustc_public::run_with_tcx!(&rustc_args, run);
+    let res = rustc_driver::catch_fatal_errors(|| {
+        rustc_driver::run_compiler(&rustc_args, &mut Driver);
+    });
+    // Errors like new findings in the check mode have been emitted.
+    if res.is_err() {
+        std::process::exit(1);
+    }
+}
+
+struct Driver;
+
+impl rustc_driver::Callbacks for Driver {
+    fn config(&mut self, config: &mut rustc_interface::interface::Config) {
+        config.register_lints = Some(Box::new(|_, store| lint::register(store)));
+        config.override_queries = Some(lint::override_queries);
+    }
+
+    fn after_analysis(
+        &mut self,
+        _: &rustc_interface::interface::Compiler,
+        tcx: TyCtxt<'_>,
+    ) -> Compilation {
+        let flow = rustc_public::rustc_internal::run(tcx, || run(tcx)).unwrap();
+        lint::check_expectations(tcx);
+        match flow {
+            ControlFlow::Continue(()) => Compilation::Continue,
+            ControlFlow::Break(()) => Compilation::Stop,
+        }
+    }
 }
 
 fn run(tcx: TyCtxt) -> ControlFlow<(), ()> {
     let local_crate = rustc_public::local_crate();
     let fn_defs = local_crate.fn_defs();
 
-    let navi = info_mod::navi(tcx);
+    let mut navi = info_mod::navi(tcx);
 
     let mut cache_adt = Default::default();
     let writer = output::Writer::new(&local_crate.name);
     let mut map_fn = FxIndexMap::with_capacity_and_hasher(fn_defs.len(), Default::default());
 
+    let mut map_leaf = FxIndexMap::default();
+
     let mut out_callers = Vec::with_capacity(fn_defs.len());
     let mut out_adts = Vec::with_capacity(fn_defs.len());
+    let mut ids = output::ids::Ids::default();
 
     for fn_def in fn_defs {
         if let Some(body) = fn_def.body() {
             let v_sp = get_tags(fn_def);
 
             let collector = analyze_fn_def::collect(&body);
-            let finfo = info_fn::FnInfo::new(collector, &body, v_sp.into(), &mut cache_adt, tcx);
+            let finfo = info_fn::FnInfo::new(
+                collector,
+                &body,
+                fn_def.def_id(),
+                v_sp.into(),
+                &mut cache_adt,
+                tcx,
+            );
 
             let finfo = &*map_fn.entry(fn_def).or_insert(finfo);
 
             let out_caller = output::caller::Caller::new(fn_def, finfo, tcx, &navi);
             out_callers.push(out_caller);
+        } else if let Some(leaf) = info_fn::LeafInfo::new(fn_def, tcx) {
+            // FFI and intrinsics have no body, but are terminal nodes in the graph.
+            let out_caller = output::caller::Caller::new_leaf(fn_def, leaf.clone(), tcx, &navi);
+            out_callers.push(out_caller);
+            map_leaf.insert(fn_def, leaf);
         }
     }
 
+    // Compare against the baseline if `UPG_BASELINE` is set.
+    check::check(&map_fn, tcx);
+    // Emit warnings if `UPG_LINT` is set.
+    lint::lint(&map_fn, tcx);
+
+    let map_static = info_static::static_info(&map_fn, &mut cache_adt, tcx);
+
     // Write src, mir, doc to disk.
-    output::fn_::dump(&map_fn, tcx, &writer);
+    output::fn_::dump(&map_fn, &map_leaf, &map_static, tcx, &writer);
+
+    for (&item, static_info) in &map_static {
+        let out_static = output::static_::Static::new(item, static_info, tcx, &navi);
+        out_static.dump(&writer, tcx);
+        ids.static_(&out_static);
+    }
 
     let map_adt = info_adt::adt_info(&map_fn, tcx);
     for (adt, adt_info) in &map_adt {
@@ -67,15 +133,39 @@ fn run(tcx: TyCtxt) -> ControlFlow<(), ()> {
         out_adts.push(out_adt);
     }
     let adt_fn_collecor = info_adt::AdtFnCollector::new(&map_adt, &map_fn, tcx);
+    let map_panic = info_panic::panic_paths(&map_fn, &map_adt, tcx);
 
+    // Adts written to `adt/<key>.json`, which signatures link to.
+    let adt_ids: utils::FxHashSet<&str> = out_adts.iter().map(|a| a.stable_id.as_str()).collect();
     for out_func in &mut out_callers {
         out_func.update_adt_fn(&adt_fn_collecor, tcx);
+        out_func.sig.link_adts(&adt_ids);
+        out_func.update_panic_paths(&map_panic, tcx);
         out_func.dump(&writer);
+        ids.caller(out_func);
     }
     for out_adt in &mut out_adts {
         out_adt.dump(&writer);
+        ids.adt(out_adt);
     }
+    let map_trait = info_trait::trait_info(tcx);
+    let out_traits = output::trait_::Traits::new(&map_trait, tcx, &navi);
+    writer.dump_json("", "traits", &out_traits);
+    ids.traits(&out_traits);
+
+    // Stable ids of items referenced by names in other files.
+    ids.sort();
+    writer.dump_json("", "ids", &ids);
+    writer.dump_json("", "keys", &ids.keys());
+
+    navi.fill_stats(&map_fn, &map_leaf, tcx);
     writer.dump_json("navi", "tree", &navi);
+    writer.dump_json("", "diagnostics", &diagnostics::take());
+
+    if writer.is_sarif() {
+        let sarif = output::sarif::Sarif::new(&map_fn, &map_adt, tcx);
+        writer.dump_sarif(&sarif);
+    }
 
     if std::env::var("UPG_CONTINUE").ok().is_some_and(|s| s != "0") {
         // Emit artifacts: this is necessary for crates that has dependencies.
@@ -85,9 +175,8 @@ fn run(tcx: TyCtxt) -> ControlFlow<(), ()> {
     }
 }
 
-fn get_tags(fn_def: rustc_public::ty::FnDef) -> Vec<safety_parser::safety::PropertiesAndReason> {
-    fn_def
-        .all_tool_attrs()
+fn get_tags(item: impl CrateDef) -> Vec<safety_parser::safety::PropertiesAndReason> {
+    item.all_tool_attrs()
         .iter()
         .flat_map(|attr| safety_parser::safety::parse_attr_and_get_properties(attr.as_str()))
         .collect()
@@ -4,23 +4,41 @@ use std::{fs, io, path::PathBuf};
 pub mod adt;
 pub mod caller;
 pub mod fn_;
+pub mod ids;
+pub mod sarif;
+pub mod signature;
+pub mod static_;
+pub mod trait_;
 pub mod utils;
 
 pub enum Writer {
-    BaseDir(PathBuf),
+    BaseDir {
+        dir: PathBuf,
+        compact: bool,
+    },
     Stdout,
+    /// Only a SARIF report is written to `upg.sarif` in the base directory, or stdout.
+    Sarif {
+        dir: Option<PathBuf>,
+    },
 }
 
 impl Writer {
     pub fn new(crate_name: &str) -> Self {
+        // Set by `cargo upg --format`.
+        let format = std::env::var("UPG_FORMAT").unwrap_or_default();
+        if format == "sarif" {
+            let dir = base_dir(crate_name);
+            if let Some(dir) = &dir {
+                create_dir_all(dir);
+            }
+            return Writer::Sarif { dir };
+        }
         match base_dir(crate_name) {
             Some(dir) => {
-                match fs::create_dir_all(&dir) {
-                    Ok(()) => (),
-                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => (),
-                    Err(err) => panic!("The directory {dir:?} is not created: {err}"),
-                }
-                Writer::BaseDir(dir)
+                create_dir_all(&dir);
+                let compact = format == "compact";
+                Writer::BaseDir { dir, compact }
             }
             None => Writer::Stdout,
         }
@@ -28,7 +46,7 @@ impl Writer {
 
     pub fn dump_json(&self, parent: &str, fname_stem: &str, data: &impl Serialize) {
         match self {
-            Writer::BaseDir(dir) => {
+            Writer::BaseDir { dir, compact } => {
                 let parent = dir.join(parent);
                 match fs::create_dir(&parent) {
                     Ok(()) => (),
@@ -36,10 +54,11 @@ impl Writer {
                     Err(err) => eprintln!("The directory {parent:?} is not created: {err}"),
                 }
 
-                let mut file_path = parent.join(fname_stem);
-                file_path.set_extension("json");
+                // Not `set_extension`, because storage keys contain dots.
+                let file_path = parent.join(format!("{fname_stem}.json"));
 
                 match fs::File::create(&file_path) {
+                    Ok(file) if *compact => serde_json::to_writer(file, data).unwrap(),
                     Ok(file) => serde_json::to_writer_pretty(file, data).unwrap(),
                     Err(err) => eprintln!("{file_path:?} {err:?}"),
                 }
@@ -51,8 +70,38 @@ impl Writer {
                 serde_json::to_writer_pretty(&mut *stdout, data).unwrap();
                 _ = writeln!(stdout);
             }
+            Writer::Sarif { .. } => (),
         }
     }
+
+    pub fn is_sarif(&self) -> bool {
+        matches!(self, Writer::Sarif { .. })
+    }
+
+    pub fn dump_sarif(&self, sarif: &sarif::Sarif) {
+        match self {
+            Writer::Sarif { dir: Some(dir) } => {
+                let file_path = dir.join("upg.sarif");
+                match fs::File::create(&file_path) {
+                    Ok(file) => serde_json::to_writer_pretty(file, sarif).unwrap(),
+                    Err(err) => eprintln!("{file_path:?} {err:?}"),
+                }
+            }
+            Writer::Sarif { dir: None } => {
+                serde_json::to_writer_pretty(io::stdout(), sarif).unwrap();
+                println!();
+            }
+            _ => (),
+        }
+    }
+}
+
+fn create_dir_all(dir: &PathBuf) {
+    match fs::create_dir_all(dir) {
+        Ok(()) => (),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => (),
+        Err(err) => panic!("The directory {dir:?} is not created: {err}"),
+    }
 }
 
 /// The base directory `$UPG_DIR/crate_name` to store JSONs data.
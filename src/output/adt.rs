@@ -1,7 +1,7 @@
 use super::{Writer, utils};
 use crate::{
     FxIndexMap,
-    adt::Adt as RawAdt,
+    adt::{Adt as RawAdt, VaraintFieldIdx},
     info_adt::{Access as RawAccess, AdtInfo},
     output::utils::doc_internal,
 };
@@ -12,7 +12,9 @@ use serde::Serialize;
 #[derive(Debug, Serialize)]
 pub struct Adt {
     pub name: String,
-    pub constructors: Vec<String>,
+    /// Instantiated adt names, e.g. `Vec::<u8>` and `Vec::<Page>`.
+    pub instances: Vec<String>,
+    pub constructors: Vec<FnRef>,
     pub access_self_as_arg: Access,
     pub access_self_as_locals: Access,
     pub access_field: Vec<Access>,
@@ -21,6 +23,7 @@ pub struct Adt {
     pub kind: String,
     pub doc_adt: String,
     pub variant_fields: FxIndexMap<String, VariantField>,
+    pub stable_id: String,
 }
 
 impl Adt {
@@ -33,35 +36,43 @@ impl Adt {
         for vf in &*adt.variant_fields {
             let idx = format!("{:?}", vf.idx);
             let name = vf.name.to_string();
-            let old = match (vf.idx.field, vf.idx.variant) {
+            let did = match (vf.idx.field, vf.idx.variant) {
                 // unit struct: no fields
                 (None, None) => break,
                 // enum variant probably without fields
-                (None, Some(variant_idx)) => {
-                    let did = adt_def.variant(variant_idx.into()).def_id;
-                    let doc = utils::doc_internal(did, tcx);
-                    variant_fields.insert(idx, VariantField { name, doc })
-                }
-                (Some(field_idx), None) => {
-                    let variant = adt_def.variant(0u32.into());
-                    let field = variant
-                        .fields
-                        .get(rustc_abi::FieldIdx::from_u32(field_idx))
-                        .unwrap();
-                    let doc = doc_internal(field.did, tcx);
-                    variant_fields.insert(idx, VariantField { name, doc })
-                }
-                (Some(_), Some(_)) => {
-                    let doc = String::new();
-                    variant_fields.insert(idx, VariantField { name, doc })
+                (None, Some(variant_idx)) => adt_def.variant(variant_idx.into()).def_id,
+                (Some(field_idx), variant_idx) => {
+                    let variant = adt_def.variant(variant_idx.unwrap_or(0).into());
+                    let field = rustc_abi::FieldIdx::from_u32(field_idx);
+                    variant.fields.get(field).unwrap().did
                 }
             };
+            // Fields of enum variants have no doc for now.
+            let doc = match (vf.idx.field, vf.idx.variant) {
+                (Some(_), Some(_)) => String::new(),
+                _ => doc_internal(did, tcx),
+            };
+            let stable_id = utils::stable_id_internal(did, tcx);
+            let old = variant_fields.insert(
+                idx,
+                VariantField {
+                    name,
+                    doc,
+                    stable_id,
+                    idx: vf.idx,
+                },
+            );
             assert!(old.is_none(), "{adt_def:?}: {vf:?} has been inserted")
         }
 
         Adt {
             name: utils::name(adt.def, tcx),
-            constructors: v_fn_name(&info.constructors, tcx),
+            instances: info
+                .instances
+                .iter()
+                .map(|args| utils::name_with_args(adt.def, args, tcx))
+                .collect(),
+            constructors: v_fn_ref(&info.constructors, tcx),
             access_self_as_arg: Access::new(&info.as_argument, tcx),
             access_self_as_locals: Access::new(&info.otherwise, tcx),
             access_field: info.fields.iter().map(|f| Access::new(f, tcx)).collect(),
@@ -70,33 +81,48 @@ impl Adt {
             kind,
             doc_adt: utils::doc(adt.def, tcx),
             variant_fields,
+            stable_id: utils::stable_id(adt.def, tcx),
         }
     }
 
     pub fn dump(&self, writer: &Writer) {
-        writer.dump_json("adt", &self.name, self);
+        let key = utils::storage_key(&self.name, &self.stable_id);
+        writer.dump_json("adt", &key, self);
     }
 }
 
 #[derive(Debug, Serialize)]
 pub struct Access {
-    pub read: Vec<String>,
-    pub write: Vec<String>,
-    pub other: Vec<String>,
+    pub read: Vec<FnRef>,
+    pub write: Vec<FnRef>,
+    pub other: Vec<FnRef>,
 }
 
 impl Access {
-    fn new(raw: &RawAccess, tcx: TyCtxt) -> Access {
+    pub fn new(raw: &RawAccess, tcx: TyCtxt) -> Access {
         Access {
-            read: v_fn_name(&raw.read, tcx),
-            write: v_fn_name(&raw.write, tcx),
-            other: v_fn_name(&raw.other, tcx),
+            read: v_fn_ref(&raw.read, tcx),
+            write: v_fn_ref(&raw.write, tcx),
+            other: v_fn_ref(&raw.other, tcx),
         }
     }
 }
 
-fn v_fn_name(v: &[FnDef], tcx: TyCtxt) -> Vec<String> {
-    let mut v: Vec<_> = v.iter().map(|c| utils::name(*c, tcx)).collect();
+/// A function by name, and the stable id to tell apart functions sharing the name.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct FnRef {
+    pub name: String,
+    pub stable_id: String,
+}
+
+fn v_fn_ref(v: &[FnDef], tcx: TyCtxt) -> Vec<FnRef> {
+    let mut v: Vec<_> = v
+        .iter()
+        .map(|&f| FnRef {
+            name: utils::name(f, tcx),
+            stable_id: utils::stable_id(f, tcx),
+        })
+        .collect();
     v.sort_unstable();
     v
 }
@@ -105,4 +131,7 @@ fn v_fn_name(v: &[FnDef], tcx: TyCtxt) -> Vec<String> {
 pub struct VariantField {
     pub name: String,
     pub doc: String,
+    pub stable_id: String,
+    #[serde(skip)]
+    pub idx: VaraintFieldIdx,
 }
@@ -1,8 +1,12 @@
-use super::{Writer, utils};
+use super::{Writer, signature::Signature, utils};
 use crate::{
-    info_adt::{AdtFnCollector, AdtFnKindMap, OutAdtFnKindInfo, out_adt_fn_kind_info},
-    info_fn::FnInfo,
+    info_adt::{
+        AdtFnCollector, AdtFnKindMap, FieldAccessKind, OutAdtFnKindInfo, out_adt_fn_kind_info,
+    },
+    info_call::{self, ArgSource, GuardKind},
+    info_fn::{FnInfo, Implicit, LeafInfo},
     info_mod::Navigation,
+    info_panic::{self, PathPointKind},
     utils::FxIndexMap,
 };
 use rustc_middle::ty::TyCtxt;
@@ -16,10 +20,25 @@ pub struct Caller {
     #[serde(flatten)]
     pub meta: utils::Meta,
     pub safe: bool,
-    /// Direct callees. The key is generic FnDef name, the value is Instance info.
+    #[serde(flatten)]
+    pub fn_meta: utils::FnMeta,
+    pub sig: Signature,
+    /// Names of safety tags on the function.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Present if the function is FFI or an intrinsic without body to analyze.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf: Option<LeafInfo>,
+    /// Direct callees. The key is the stable id of the generic FnDef, the value is Instance
+    /// info.
     pub callees: FxIndexMap<String, CalleeInfo>,
     /// How caller interacts with adts.
     pub adts: AdtFieldInfo,
+    /// Statics and consts accessed by the caller. The key is the stable id of the item.
+    pub statics: FxIndexMap<String, StaticAccess>,
+    /// Paths where a panic may occur after a possibly broken invariant.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub panic_paths: Vec<PanicPath>,
     pub path: OutputPath,
 }
 
@@ -29,16 +48,40 @@ impl Caller {
             fn_def,
             meta: utils::Meta::new(fn_def, tcx),
             safe: utils::is_safe(fn_def),
-            callees: output_callee(info),
+            fn_meta: utils::FnMeta::new(fn_def, tcx),
+            sig: Signature::new(fn_def, tcx),
+            tags: utils::tag_names(fn_def),
+            leaf: LeafInfo::new(fn_def, tcx),
+            callees: output_callee(info, tcx),
+            adts: Default::default(),
+            statics: output_statics(info, tcx),
+            panic_paths: Vec::new(),
+            path: def_path(fn_def.def_id(), tcx, navi),
+        }
+    }
+
+    /// A function without body, so it has no callees.
+    pub fn new_leaf(fn_def: FnDef, leaf: LeafInfo, tcx: TyCtxt, navi: &Navigation) -> Self {
+        Caller {
+            fn_def,
+            meta: utils::Meta::new(fn_def, tcx),
+            safe: utils::is_safe(fn_def),
+            fn_meta: utils::FnMeta::new(fn_def, tcx),
+            sig: Signature::new(fn_def, tcx),
+            tags: utils::tag_names(fn_def),
+            leaf: Some(leaf),
+            callees: Default::default(),
             adts: Default::default(),
+            statics: Default::default(),
+            panic_paths: Vec::new(),
             path: def_path(fn_def.def_id(), tcx, navi),
         }
     }
 
     pub fn update_adt_fn(&mut self, adt_fn_collecor: &AdtFnCollector, tcx: TyCtxt) {
-        for (callee, info) in &mut self.callees {
+        for info in self.callees.values_mut() {
             if let Some(map) = adt_fn_collecor.caller_callee_map.get(&self.fn_def)
-                && let Some(adt_map) = map.get(callee.as_str())
+                && let Some(adt_map) = map.get(&info.fn_def)
             {
                 add_field_info(&mut info.adt, adt_map, tcx);
             }
@@ -50,15 +93,28 @@ impl Caller {
         }
     }
 
+    pub fn update_panic_paths(
+        &mut self,
+        map_panic: &FxIndexMap<FnDef, Vec<info_panic::PanicPath>>,
+        tcx: TyCtxt,
+    ) {
+        if let Some(paths) = map_panic.get(&self.fn_def) {
+            self.panic_paths = paths.iter().map(|p| PanicPath::new(p, tcx)).collect();
+        }
+    }
+
     pub fn dump(&self, writer: &Writer) {
-        writer.dump_json(&self.meta.name, "caller", self);
+        writer.dump_json(&self.meta.storage_key(), "caller", self);
     }
 }
 
 fn add_field_info(adt_field_info: &mut AdtFieldInfo, adt_map: &AdtFnKindMap, tcx: TyCtxt) {
     *adt_field_info = adt_map
         .iter()
-        .map(|(adt, fn_kind)| (utils::name(*adt, tcx), out_adt_fn_kind_info(fn_kind)))
+        .map(|(&adt, fn_kind)| {
+            let info = out_adt_fn_kind_info(fn_kind, utils::name(adt, tcx));
+            (utils::stable_id(adt, tcx), info)
+        })
         .collect();
 }
 
@@ -69,11 +125,11 @@ pub enum OutputPath {
     External(Box<str>),
 }
 
-fn def_path(def_id: DefId, tcx: TyCtxt, navi: &Navigation) -> OutputPath {
+pub fn def_path(def_id: DefId, tcx: TyCtxt, navi: &Navigation) -> OutputPath {
     let did = internal(tcx, def_id);
     let def_path_str = tcx.def_path_str(did);
     let def_path_str_maybe_local = format!("{}::{def_path_str}", navi.crate_root());
-    match navi.name_to_id(&def_path_str_maybe_local) {
+    match navi.node_id(&utils::stable_id_internal(did, tcx)) {
         Some(_) => OutputPath::Local(def_path_str_maybe_local.into()),
         None => OutputPath::External(def_path_str.into()),
     }
@@ -81,21 +137,163 @@ fn def_path(def_id: DefId, tcx: TyCtxt, navi: &Navigation) -> OutputPath {
 
 #[derive(Debug, Serialize)]
 pub struct CalleeInfo {
+    #[serde(skip)]
+    pub fn_def: FnDef,
+    /// The generic FnDef name.
+    pub name: String,
+    /// Instantiated callee names of explicit uses, e.g. `Vec::<u8>::set_len` and
+    /// `Vec::<Page>::set_len`. Empty if the callee is only invoked implicitly.
+    pub instance_name: Vec<String>,
     pub safe: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf: Option<LeafInfo>,
+    /// Set if the callee is invoked implicitly, like drop glue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implicit: Option<Implicit>,
+    /// Instantiated callee names of implicit invocations.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub implicit_instance_name: Vec<String>,
     pub adt: AdtFieldInfo,
+    /// Explicit call sites in the caller.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallSite>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CallSite {
+    pub span: String,
+    /// The basic block terminated by the call in the caller's MIR.
+    pub bb: usize,
+    pub in_unsafe: bool,
+    /// Sources of each argument passed to the callee.
+    pub args: Vec<Vec<ArgSource>>,
+    /// Branches and assertions dominating a call to an unsafe callee.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub guards: Vec<Guard>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct Guard {
+    pub kind: GuardKind,
+    pub span: String,
+    pub bb: usize,
+    pub src: String,
+    /// The branch taken to reach the call, or the expected value of an assertion.
+    pub taken: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub msg: Option<&'static str>,
+    /// Sources of the condition.
+    pub sources: Vec<ArgSource>,
+}
+
+impl Guard {
+    fn new(guard: &info_call::Guard, tcx: TyCtxt) -> Self {
+        Guard {
+            kind: guard.kind,
+            span: utils::span_string(guard.span, tcx),
+            bb: guard.bb,
+            src: guard.src.clone(),
+            taken: guard.taken.clone(),
+            msg: guard.msg,
+            sources: guard.sources.clone(),
+        }
+    }
+}
+
+impl CallSite {
+    fn new(call: &info_call::CallSite, tcx: TyCtxt) -> Self {
+        CallSite {
+            span: utils::span_string(call.span, tcx),
+            bb: call.bb,
+            in_unsafe: call.in_unsafe,
+            args: call.args.clone(),
+            guards: call.guards.iter().map(|g| Guard::new(g, tcx)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PanicPath {
+    /// Where the invariant is possibly broken.
+    pub change: PathPoint,
+    /// The first terminator that may panic after the change.
+    pub panic: PathPoint,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PathPoint {
+    pub kind: PathPointKind,
+    pub target: String,
+    pub span: String,
+    pub bb: usize,
+}
+
+impl PanicPath {
+    fn new(path: &info_panic::PanicPath, tcx: TyCtxt) -> Self {
+        let point = |p: &info_panic::PathPoint| PathPoint {
+            kind: p.kind,
+            target: p.target.clone(),
+            span: utils::span_string(p.span, tcx),
+            bb: p.bb,
+        };
+        PanicPath {
+            change: point(&path.change),
+            panic: point(&path.panic),
+        }
+    }
+}
+
+/// The key is the stable id of the adt.
 pub type AdtFieldInfo = FxIndexMap<String, OutAdtFnKindInfo>;
 
-pub fn output_callee(finfo: &FnInfo) -> FxIndexMap<String, CalleeInfo> {
+pub fn output_callee(finfo: &FnInfo, tcx: TyCtxt) -> FxIndexMap<String, CalleeInfo> {
     let mut map = FxIndexMap::<String, CalleeInfo>::default();
     for (fn_def, info) in &finfo.callees {
         let fn_def = *fn_def;
         let callee_info = CalleeInfo {
+            fn_def,
+            name: info.non_instance_name.clone(),
+            instance_name: info
+                .instances
+                .iter()
+                .map(|args| utils::name_with_args(fn_def, args, tcx))
+                .collect(),
             safe: utils::is_safe(fn_def),
+            leaf: LeafInfo::new(fn_def, tcx),
+            implicit: info.implicit,
+            implicit_instance_name: info
+                .implicit_instances
+                .iter()
+                .map(|args| utils::name_with_args(fn_def, args, tcx))
+                .collect(),
             adt: Default::default(),
+            calls: info
+                .calls
+                .iter()
+                .map(|call| CallSite::new(call, tcx))
+                .collect(),
         };
-        map.insert(info.non_instance_name.clone(), callee_info);
+        map.insert(utils::stable_id(fn_def, tcx), callee_info);
     }
     map
 }
+
+#[derive(Debug, Serialize)]
+pub struct StaticAccess {
+    pub name: String,
+    pub kind: FieldAccessKind,
+}
+
+pub fn output_statics(finfo: &FnInfo, tcx: TyCtxt) -> FxIndexMap<String, StaticAccess> {
+    finfo
+        .statics
+        .iter()
+        .map(|(&item, &kind)| {
+            let access = StaticAccess {
+                name: utils::name(item, tcx),
+                kind,
+            };
+            (utils::stable_id(item, tcx), access)
+        })
+        .collect()
+}
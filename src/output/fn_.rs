@@ -1,14 +1,25 @@
 use super::{
     Writer,
-    utils::{self, Meta},
+    utils::{self, FnMeta, Meta},
+};
+use crate::{
+    FxIndexMap, FxIndexSet,
+    info_fn::{FnInfo, LeafInfo},
+    info_static::StaticInfo,
 };
-use crate::{FxIndexMap, FxIndexSet, info_fn::FnInfo};
 use rustc_middle::ty::TyCtxt;
-use rustc_public::{mir::Body, ty::FnDef};
+use rustc_public::{CrateDef, CrateItem, mir::Body, ty::FnDef};
 use serde::Serialize;
 
-pub fn dump(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt, writer: &Writer) {
-    let mut unsafe_fns = FxIndexMap::<String, Unsafe>::with_capacity_and_hasher(
+pub fn dump(
+    map_fn: &FxIndexMap<FnDef, FnInfo>,
+    map_leaf: &FxIndexMap<FnDef, LeafInfo>,
+    map_static: &FxIndexMap<CrateItem, StaticInfo>,
+    tcx: TyCtxt,
+    writer: &Writer,
+) {
+    // Keyed by stable ids, because functions may share a name.
+    let mut unsafe_fns = FxIndexMap::<String, UnsafeFn>::with_capacity_and_hasher(
         map_fn.len() / 4,
         Default::default(),
     );
@@ -25,41 +36,77 @@ pub fn dump(map_fn: &FxIndexMap<FnDef, FnInfo>, tcx: TyCtxt, writer: &Writer) {
         // Collect all unsafe fns, including
         // * unsafe caller
         // * or safe fn with unsafe callees
+        // Drop glue is an unsafe fn, but implicit drops are not unsafe calls.
         let unsafe_caller = !utils::is_safe(caller);
-        let unsafe_callee = info.callees.keys().any(|&f| !utils::is_safe(f));
+        let unsafe_callee = info
+            .callees
+            .iter()
+            .any(|(&f, callee)| callee.is_explicit() && !utils::is_safe(f));
         if unsafe_caller | unsafe_callee {
-            let fn_name = utils::name(caller, tcx);
             let kind = match (unsafe_caller, unsafe_callee) {
                 (true, true) => Unsafe::Both,
                 (true, false) => Unsafe::Caller,
                 (false, true) => Unsafe::Callee,
                 (false, false) => unreachable!(),
             };
-            unsafe_fns.insert(fn_name, kind);
+            unsafe_fns.insert(
+                utils::stable_id(caller, tcx),
+                UnsafeFn::new(caller, kind, tcx),
+            );
         }
     }
 
-    // Sort unsafe fns.
-    unsafe_fns.sort_unstable_keys();
+    // Functions called in static and const initializers.
+    for info in map_static.values() {
+        all_fns.extend(info.init.callees.keys().copied());
+    }
+
+    // FFI and intrinsics are unsafe fns without body.
+    for &leaf in map_leaf.keys() {
+        all_fns.insert(leaf);
+        if !utils::is_safe(leaf) {
+            let unsafe_fn = UnsafeFn::new(leaf, Unsafe::Leaf, tcx);
+            unsafe_fns.insert(utils::stable_id(leaf, tcx), unsafe_fn);
+        }
+    }
+
+    // Sort unsafe fns by names.
+    unsafe_fns.sort_unstable_by(|id1, f1, id2, f2| (&f1.name, id1).cmp(&(&f2.name, id2)));
     writer.dump_json("navi", "unsafe_fns", &unsafe_fns);
 
     for fn_def in all_fns {
-        let doc = Documentation::new(fn_def, tcx);
+        let doc = Documentation::new_fn(fn_def, tcx);
 
-        let name = &doc.meta.name;
-        writer.dump_json(name, "doc", &doc);
+        let key = &doc.meta.storage_key();
+        writer.dump_json(key, "doc", &doc);
 
         if utils::did(fn_def, tcx).is_local()
             && let Some(body) = fn_def.body()
         {
             let mir = Mir::new(fn_def, &body, tcx);
-            writer.dump_json(name, "mir", &mir);
+            writer.dump_json(key, "mir", &mir);
 
             let src = Source::new_with_body(fn_def, &body, tcx);
-            writer.dump_json(name, "src", &src);
+            writer.dump_json(key, "src", &src);
         } else {
             let src = Source::new(fn_def, tcx);
-            writer.dump_json(name, "src", &src);
+            writer.dump_json(key, "src", &src);
+        }
+    }
+}
+
+/// An entry in `navi/unsafe_fns.json`.
+#[derive(Debug, Serialize)]
+pub struct UnsafeFn {
+    pub name: String,
+    pub kind: Unsafe,
+}
+
+impl UnsafeFn {
+    fn new(fn_def: FnDef, kind: Unsafe, tcx: TyCtxt) -> Self {
+        UnsafeFn {
+            name: utils::name(fn_def, tcx),
+            kind,
         }
     }
 }
@@ -72,6 +119,8 @@ pub enum Unsafe {
     Callee,
     /// The function is unsafe, and calles unsafe functions inside.
     Both,
+    /// The function is unsafe FFI or intrinsic without body.
+    Leaf,
 }
 
 #[derive(Debug, Serialize)]
@@ -82,17 +131,17 @@ pub struct Source {
 }
 
 impl Source {
-    pub fn new_with_body(fn_def: FnDef, body: &Body, tcx: TyCtxt) -> Self {
+    pub fn new_with_body<T: CrateDef + Copy>(item: T, body: &Body, tcx: TyCtxt) -> Self {
         Self {
-            meta: Meta::new(fn_def, tcx),
+            meta: Meta::new(item, tcx),
             src: utils::src_from_span(body.span, tcx),
         }
     }
 
-    pub fn new(fn_def: FnDef, tcx: TyCtxt) -> Self {
+    pub fn new<T: CrateDef + Copy>(item: T, tcx: TyCtxt) -> Self {
         Self {
-            meta: Meta::new(fn_def, tcx),
-            src: utils::src(fn_def, tcx),
+            meta: Meta::new(item, tcx),
+            src: utils::src(item, tcx),
         }
     }
 }
@@ -105,8 +154,8 @@ pub struct Mir {
 }
 
 impl Mir {
-    pub fn new(fn_def: FnDef, body: &Body, tcx: TyCtxt) -> Self {
-        let meta = Meta::new(fn_def, tcx);
+    pub fn new<T: CrateDef + Copy>(item: T, body: &Body, tcx: TyCtxt) -> Self {
+        let meta = Meta::new(item, tcx);
         let mut buf = Vec::with_capacity(1024);
         _ = body.dump(&mut buf, &meta.name);
         let mir = String::from_utf8(buf).unwrap_or_default();
@@ -118,14 +167,25 @@ impl Mir {
 pub struct Documentation {
     #[serde(flatten)]
     pub meta: Meta,
+    /// Present for functions.
+    #[serde(flatten)]
+    pub fn_meta: Option<FnMeta>,
     pub doc: String,
 }
 
 impl Documentation {
-    pub fn new(fn_def: FnDef, tcx: TyCtxt) -> Self {
+    pub fn new<T: CrateDef + Copy>(item: T, tcx: TyCtxt) -> Self {
+        Self {
+            meta: Meta::new(item, tcx),
+            fn_meta: None,
+            doc: utils::doc(item, tcx),
+        }
+    }
+
+    pub fn new_fn(fn_def: FnDef, tcx: TyCtxt) -> Self {
         Self {
-            meta: Meta::new(fn_def, tcx),
-            doc: utils::doc(fn_def, tcx),
+            fn_meta: Some(FnMeta::new(fn_def, tcx)),
+            ..Self::new(fn_def, tcx)
         }
     }
 }
@@ -0,0 +1,130 @@
+//! `ids.json`: stable ids of functions, ADTs, fields, statics, traits, and tags referenced by
+//! other output files, with their kinds, display names, and storage keys.
+//!
+//! `keys.json`: display names to storage keys, i.e. directory and file names of items. A name
+//! maps to more than one key if items share the name.
+
+use super::{
+    adt::Adt,
+    caller::Caller,
+    static_::Static,
+    trait_::Traits,
+    utils::{storage_key, tag_names, tag_stable_id},
+};
+use crate::FxIndexMap;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum IdKind {
+    Fn,
+    Adt,
+    Variant,
+    Field,
+    Static,
+    Trait,
+    Impl,
+    Tag,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdEntry {
+    pub kind: IdKind,
+    pub name: String,
+    /// The stable id of the adt for variants and fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// The storage key for items with outputs: functions, adts, and statics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// Stable id to the item.
+#[derive(Debug, Default, Serialize)]
+pub struct Ids {
+    #[serde(flatten)]
+    map: FxIndexMap<String, IdEntry>,
+}
+
+impl Ids {
+    fn insert(&mut self, stable_id: &str, kind: IdKind, name: &str, parent: Option<&str>) {
+        if !self.map.contains_key(stable_id) {
+            let has_output = matches!(kind, IdKind::Fn | IdKind::Adt | IdKind::Static);
+            let entry = IdEntry {
+                kind,
+                name: name.to_owned(),
+                parent: parent.map(String::from),
+                key: has_output.then(|| storage_key(name, stable_id)),
+            };
+            self.map.insert(stable_id.to_owned(), entry);
+        }
+    }
+
+    fn insert_tags(&mut self, tags: impl IntoIterator<Item = impl AsRef<str>>) {
+        for tag in tags {
+            let tag = tag.as_ref();
+            self.insert(&tag_stable_id(tag), IdKind::Tag, tag, None);
+        }
+    }
+
+    /// The function, its tags, and callees.
+    pub fn caller(&mut self, caller: &Caller) {
+        let meta = &caller.meta;
+        self.insert(&meta.stable_id, IdKind::Fn, &meta.name, None);
+        self.insert_tags(&caller.tags);
+        for (id, callee) in &caller.callees {
+            self.insert(id, IdKind::Fn, &callee.name, None);
+        }
+    }
+
+    pub fn adt(&mut self, adt: &Adt) {
+        let id = &adt.stable_id;
+        self.insert(id, IdKind::Adt, &adt.name, None);
+        for field in adt.variant_fields.values() {
+            let kind = if field.idx.field.is_none() {
+                IdKind::Variant
+            } else {
+                IdKind::Field
+            };
+            let name = format!("{}.{}", adt.name, field.name);
+            self.insert(&field.stable_id, kind, &name, Some(id));
+        }
+    }
+
+    pub fn static_(&mut self, item: &Static) {
+        let meta = &item.meta;
+        self.insert(&meta.stable_id, IdKind::Static, &meta.name, None);
+        for (id, callee) in &item.callees {
+            self.insert(id, IdKind::Fn, &callee.name, None);
+        }
+    }
+
+    pub fn traits(&mut self, traits: &Traits) {
+        for tr in &traits.traits {
+            self.insert(&tr.meta.stable_id, IdKind::Trait, &tr.meta.name, None);
+            self.insert_tags(tag_names(tr.trait_def));
+            for imp in &tr.impls {
+                self.insert(&imp.meta.stable_id, IdKind::Impl, &imp.meta.name, None);
+            }
+        }
+    }
+
+    /// Data written to `keys.json`: names to storage keys. Items may share a name, so a name
+    /// maps to keys of all of them in the order of stable ids.
+    pub fn keys(&self) -> FxIndexMap<&str, Vec<&str>> {
+        let mut keys = FxIndexMap::<_, Vec<_>>::default();
+        for entry in self.map.values() {
+            if let Some(key) = &entry.key {
+                keys.entry(entry.name.as_str())
+                    .or_default()
+                    .push(key.as_str());
+            }
+        }
+        keys
+    }
+
+    /// Sort by names for readability.
+    pub fn sort(&mut self) {
+        self.map
+            .sort_unstable_by(|id1, e1, id2, e2| (&e1.name, id1).cmp(&(&e2.name, id2)));
+    }
+}
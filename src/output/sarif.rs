@@ -0,0 +1,436 @@
+//! SARIF 2.1.0 report for code-scanning integrations, written when `UPG_FORMAT=sarif`.
+
+use super::utils::{self, SpanLocation, is_safe, span_location, tag_names};
+use crate::{
+    FxIndexMap, FxIndexSet,
+    adt::Adt,
+    check::{has_safety_comment, unsafe_blocks},
+    info_adt::AdtInfo,
+    info_fn::FnInfo,
+};
+use rustc_middle::ty::TyCtxt;
+use rustc_public::{
+    CrateDef,
+    mir::{LocalDecl, Place, ProjectionElem},
+    rustc_internal::internal,
+    ty::{AdtDef, FnDef, RigidTy, TyKind},
+};
+use serde::Serialize;
+
+#[derive(Clone, Copy)]
+enum Rule {
+    UnsafeCall,
+    LeakedTag,
+    UndocumentedUnsafeBlock,
+    ExogenousWrite,
+}
+
+impl Rule {
+    const ALL: [Rule; 4] = [
+        Rule::UnsafeCall,
+        Rule::LeakedTag,
+        Rule::UndocumentedUnsafeBlock,
+        Rule::ExogenousWrite,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Rule::UnsafeCall => "unsafe-call",
+            Rule::LeakedTag => "leaked-tag",
+            Rule::UndocumentedUnsafeBlock => "undocumented-unsafe-block",
+            Rule::ExogenousWrite => "exogenous-write",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Rule::UnsafeCall => "An unsafe function is called.",
+            Rule::LeakedTag => {
+                "A safety tag of the callee is neither discharged nor delegated by the unsafe caller."
+            }
+            Rule::UndocumentedUnsafeBlock => "An unsafe block has no `// SAFETY:` comment.",
+            Rule::ExogenousWrite => {
+                "A safe function writes a field that unsafe functions of the adt may rely on."
+            }
+        }
+    }
+
+    fn level(self) -> &'static str {
+        match self {
+            Rule::UnsafeCall => "note",
+            Rule::LeakedTag | Rule::UndocumentedUnsafeBlock | Rule::ExogenousWrite => "warning",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Sarif {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    /// Placeholders from `--remap-path-prefix` like `SYSROOT` to the real path prefixes.
+    #[serde(skip_serializing_if = "FxIndexMap::is_empty")]
+    original_uri_base_ids: FxIndexMap<String, BaseUri>,
+    results: Vec<SarifResult>,
+}
+
+/// An artifact location for a base id.
+#[derive(Serialize)]
+struct BaseUri {
+    /// Absent if the placeholder stands for several path prefixes, like `$CARGO` for registry
+    /// sources and git checkouts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+    description: Message,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: ToolDriver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolDriver {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<RuleDescriptor>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleDescriptor {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+#[derive(Serialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(Clone, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    /// Set for remapped paths like `$SYSROOT/lib/rustlib/src/rust/library/core/src/ptr/mod.rs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl Location {
+    fn new(span: rustc_span::Span, tcx: TyCtxt) -> Option<Self> {
+        let SpanLocation {
+            file,
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        } = span_location(span, tcx)?;
+        // A placeholder from `--remap-path-prefix` is a base uri.
+        let artifact_location = match file.strip_prefix('$').and_then(|s| s.split_once('/')) {
+            Some((base, uri)) => ArtifactLocation {
+                uri: uri.to_owned(),
+                uri_base_id: Some(base.to_owned()),
+            },
+            None => ArtifactLocation {
+                uri: file,
+                uri_base_id: None,
+            },
+        };
+        Some(Location {
+            id: None,
+            physical_location: PhysicalLocation {
+                artifact_location,
+                region: Region {
+                    start_line,
+                    start_column: start_col,
+                    end_line,
+                    end_column: end_col,
+                },
+            },
+            message: None,
+        })
+    }
+
+    fn of_item(item: impl CrateDef, tcx: TyCtxt) -> Option<Self> {
+        Location::new(internal(tcx, item.span()), tcx)
+    }
+}
+
+/// Results are pushed in the order of functions.
+struct Results<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    results: Vec<SarifResult>,
+}
+
+impl Results<'_> {
+    /// Related locations are numbered, and the message is the name of the item.
+    fn push(
+        &mut self,
+        rule: Rule,
+        text: String,
+        location: Option<Location>,
+        related: &[(FnDef, &str)],
+    ) {
+        let Some(location) = location else { return };
+        let related_locations = related
+            .iter()
+            .filter_map(|&(fn_def, role)| {
+                let mut location = Location::of_item(fn_def, self.tcx)?;
+                let name = utils::name(fn_def, self.tcx);
+                location.message = Some(Message {
+                    text: format!("{role} `{name}`"),
+                });
+                Some(location)
+            })
+            .enumerate()
+            .map(|(id, location)| Location {
+                id: Some(id),
+                ..location
+            })
+            .collect();
+        self.results.push(SarifResult {
+            rule_id: rule.id(),
+            rule_index: rule as usize,
+            level: rule.level(),
+            message: Message { text },
+            locations: vec![location],
+            related_locations,
+        });
+    }
+}
+
+impl Sarif {
+    pub fn new(
+        map_fn: &FxIndexMap<FnDef, FnInfo>,
+        map_adt: &FxIndexMap<Adt, AdtInfo>,
+        tcx: TyCtxt,
+    ) -> Self {
+        let mut results = Results {
+            tcx,
+            results: Vec::new(),
+        };
+
+        for (&caller, info) in map_fn {
+            let caller_name = utils::name(caller, tcx);
+            let caller_tags = tag_names(caller);
+            for (&callee, callee_info) in &info.callees {
+                if !callee_info.is_explicit() || is_safe(callee) {
+                    continue;
+                }
+                let callee_name = &callee_info.non_instance_name;
+                let location = match info.callee_span(callee) {
+                    Some(span) => Location::new(internal(tcx, span), tcx),
+                    None => Location::of_item(caller, tcx),
+                };
+                let chain = [(caller, "caller"), (callee, "callee")];
+
+                if !is_safe(caller) {
+                    // Tags on a safe caller are discharged by the unsafe block.
+                    for tag in tag_names(callee) {
+                        if !caller_tags.contains(&tag) {
+                            let text = format!(
+                                "`{tag}` of `{callee_name}` is not delegated to `{caller_name}`."
+                            );
+                            let location = location.clone();
+                            results.push(Rule::LeakedTag, text, location, &chain);
+                        }
+                    }
+                }
+
+                let text = format!("`{caller_name}` calls unsafe `{callee_name}`.");
+                results.push(Rule::UnsafeCall, text, location, &chain);
+            }
+
+            for (block_id, span) in unsafe_blocks(caller, tcx) {
+                if !has_safety_comment(block_id, span, tcx) {
+                    let text = format!("An unsafe block in `{caller_name}` has no safety comment.");
+                    let location = Location::new(span, tcx);
+                    results.push(Rule::UndocumentedUnsafeBlock, text, location, &[]);
+                }
+            }
+        }
+
+        for (adt, info) in map_adt {
+            exogenous_writes(adt, info, map_fn, &mut results);
+        }
+
+        let rules = Rule::ALL
+            .iter()
+            .map(|rule| RuleDescriptor {
+                id: rule.id(),
+                short_description: Message {
+                    text: rule.description().to_owned(),
+                },
+                default_configuration: Configuration {
+                    level: rule.level(),
+                },
+            })
+            .collect();
+        Sarif {
+            schema: "https://json.schemastore.org/sarif-2.1.0.json",
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: ToolDriver {
+                        name: "unsafety-propagation-graph",
+                        information_uri: "https://github.com/os-checker/unsafety-propagation-graph",
+                        rules,
+                    },
+                },
+                original_uri_base_ids: original_uri_base_ids(tcx),
+                results: results.results,
+            }],
+        }
+    }
+}
+
+/// Base ids are placeholders like `$SYSROOT` that `--remap-path-prefix` maps path prefixes to.
+fn original_uri_base_ids(tcx: TyCtxt) -> FxIndexMap<String, BaseUri> {
+    let mut prefixes = FxIndexMap::<String, Vec<String>>::default();
+    for (from, to) in &tcx.sess.opts.remap_path_prefix {
+        let to = to.to_string_lossy();
+        if let Some(id) = to.strip_prefix('$')
+            && !id.is_empty()
+            && !id.contains(['/', '\\'])
+        {
+            let from = from.to_string_lossy().replace('\\', "/");
+            prefixes.entry(id.to_owned()).or_default().push(from);
+        }
+    }
+    prefixes
+        .into_iter()
+        .map(|(id, from)| {
+            let uri = match &*from {
+                // A base uri is absolute and ends with a slash, like `file:///home/user/`.
+                [from] => {
+                    let sep = if from.starts_with('/') { "" } else { "/" };
+                    let slash = if from.ends_with('/') { "" } else { "/" };
+                    Some(format!("file://{sep}{from}{slash}"))
+                }
+                _ => None,
+            };
+            let text = format!("`${id}` is remapped from {}.", from.join(", "));
+            let base = BaseUri {
+                uri,
+                description: Message { text },
+            };
+            (id, base)
+        })
+        .collect()
+}
+
+/// Safe functions writing fields of an adt that has unsafe functions, which may rely on
+/// invariants of the fields. Each write in a writer is a result.
+fn exogenous_writes(
+    adt: &Adt,
+    info: &AdtInfo,
+    map_fn: &FxIndexMap<FnDef, FnInfo>,
+    results: &mut Results,
+) {
+    let tcx = results.tcx;
+    let mut unsafe_fns = FxIndexSet::default();
+    let accesses = [&info.as_argument, &info.otherwise].into_iter();
+    for access in accesses.chain(&info.fields) {
+        let fns = access.read.iter().chain(&access.write).chain(&access.other);
+        unsafe_fns.extend(fns.copied().filter(|&f| !is_safe(f)));
+    }
+    unsafe_fns.extend(info.constructors.iter().copied().filter(|&f| !is_safe(f)));
+    if unsafe_fns.is_empty() {
+        return;
+    }
+
+    let adt_name = utils::name(adt.def, tcx);
+    let related: Vec<_> = unsafe_fns.iter().map(|&f| (f, "unsafe function")).collect();
+    for (idx, access) in info.fields.iter().enumerate() {
+        let field = match adt.variant_fields.get(idx) {
+            Some(field) => field.name.to_string(),
+            None => idx.to_string(),
+        };
+        for &writer in access.write.iter().filter(|&&f| is_safe(f)) {
+            let (Some(info), Some(body)) = (map_fn.get(&writer), writer.body()) else {
+                continue;
+            };
+            let text = format!(
+                "Safe `{}` writes `{adt_name}.{field}`, which unsafe functions may rely on.",
+                utils::name(writer, tcx)
+            );
+            let writes = info.collector.v_write.iter();
+            for write in writes.filter(|w| writes_field(&w.place, body.locals(), adt.def, idx)) {
+                let location = Location::new(internal(tcx, write.span), tcx);
+                results.push(Rule::ExogenousWrite, text.clone(), location, &related);
+            }
+        }
+    }
+}
+
+/// Whether the place projects the struct field, like `(*self).len` for `self.len = 0` or
+/// `&mut self.len`.
+fn writes_field(place: &Place, locals: &[LocalDecl], adt: AdtDef, field: usize) -> bool {
+    let mut ty = locals[place.local].ty;
+    for elem in &place.projection {
+        if let ProjectionElem::Field(idx, _) = elem
+            && *idx == field
+            && matches!(ty.kind(), TyKind::RigidTy(RigidTy::Adt(def, _)) if def == adt)
+        {
+            return true;
+        }
+        let Ok(elem_ty) = elem.ty(ty) else {
+            return false;
+        };
+        ty = elem_ty;
+    }
+    false
+}
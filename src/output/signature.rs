@@ -0,0 +1,177 @@
+//! Parameters and the return type of a function in `caller.json`.
+
+use super::utils;
+use crate::{FxIndexSet, utils::FxHashSet};
+use rustc_hir::{Node, def::DefKind, def_id::DefId as IDefId};
+use rustc_middle::ty::{self, Ty, TyCtxt, print::with_no_trimmed_paths};
+use rustc_public::ty::FnDef;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Signature {
+    pub params: Vec<Param>,
+    pub ret: SigTy,
+    /// Arguments of safety tags on the function, resolved to parameters.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag_args: Vec<TagArg>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Param {
+    /// The identifier of the parameter, or `_` for patterns and unnamed parameters.
+    pub name: String,
+    #[serde(flatten)]
+    pub ty: SigTy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SigTy {
+    pub ty: String,
+    pub kind: SigTyKind,
+    /// Adts mentioned in the type, which are linked to `adt/<key>.json`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adts: Vec<AdtLink>,
+}
+
+/// The outermost shape of the type.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum SigTyKind {
+    RawPtr,
+    RawPtrMut,
+    Ref,
+    RefMut,
+    Adt,
+    Other,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdtLink {
+    pub name: String,
+    pub stable_id: String,
+    /// The storage key of `adt/<key>.json`, which is only present if the adt has the output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// An argument of a safety tag, like `p` in `ValidPtr(p)` or `self.ptr` in `Align(self.ptr)`.
+#[derive(Debug, Serialize)]
+pub struct TagArg {
+    pub tag: String,
+    pub arg: String,
+    /// The index of the parameter the argument refers to, which is absent if the argument
+    /// doesn't start with a parameter name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param: Option<usize>,
+}
+
+impl Signature {
+    pub fn new(fn_def: FnDef, tcx: TyCtxt) -> Self {
+        let did = utils::did(fn_def, tcx);
+        let sig = tcx.fn_sig(did).instantiate_identity().skip_binder();
+        let idents = param_idents(did, tcx);
+        let params: Vec<_> = sig
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(idx, &ty)| Param {
+                name: match idents.get(idx) {
+                    Some(Some(ident)) => ident.to_string(),
+                    _ => "_".to_owned(),
+                },
+                ty: SigTy::new(ty, tcx),
+            })
+            .collect();
+        let tag_args = utils::tag_args(fn_def)
+            .into_iter()
+            .flat_map(|(tag, args)| args.into_iter().map(move |arg| (tag.clone(), arg)))
+            .map(|(tag, arg)| {
+                let param = params.iter().position(|p: &Param| p.name == arg_base(&arg));
+                TagArg { tag, arg, param }
+            })
+            .collect();
+        Signature {
+            params,
+            ret: SigTy::new(sig.output(), tcx),
+            tag_args,
+        }
+    }
+
+    /// Fill storage keys of adts with outputs, i.e. in `adt_ids` of stable ids.
+    pub fn link_adts(&mut self, adt_ids: &FxHashSet<&str>) {
+        let params = self.params.iter_mut().map(|p| &mut p.ty);
+        for ty in params.chain([&mut self.ret]) {
+            for adt in &mut ty.adts {
+                if adt_ids.contains(adt.stable_id.as_str()) {
+                    adt.key = Some(utils::storage_key(&adt.name, &adt.stable_id));
+                }
+            }
+        }
+    }
+}
+
+/// The variable an argument starts with, e.g. `p` in `*p`, `p.add(1)`, or `&mut p[0]`.
+fn arg_base(arg: &str) -> &str {
+    let arg = arg.trim_start_matches(['*', '&', ' ']);
+    let arg = arg.strip_prefix("mut ").unwrap_or(arg).trim_start();
+    let end = arg
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(arg.len());
+    &arg[..end]
+}
+
+/// Parameter identifiers, which are available for functions with bodies and declarations in
+/// traits and `extern` blocks, but not for constructors and local intrinsics.
+fn param_idents(did: IDefId, tcx: TyCtxt<'_>) -> &[Option<rustc_span::Ident>] {
+    let has_idents = match did.as_local() {
+        Some(local) => {
+            let node = tcx.hir_node_by_def_id(local);
+            node.body_id().is_some() || matches!(node, Node::TraitItem(_) | Node::ForeignItem(_))
+        }
+        None => matches!(tcx.def_kind(did), DefKind::Fn | DefKind::AssocFn),
+    };
+    if has_idents {
+        tcx.fn_arg_idents(did)
+    } else {
+        &[]
+    }
+}
+
+impl SigTy {
+    fn new(ty: Ty, tcx: TyCtxt) -> Self {
+        let kind = match ty.kind() {
+            ty::RawPtr(_, mutbl) if mutbl.is_mut() => SigTyKind::RawPtrMut,
+            ty::RawPtr(..) => SigTyKind::RawPtr,
+            ty::Ref(_, _, mutbl) if mutbl.is_mut() => SigTyKind::RefMut,
+            ty::Ref(..) => SigTyKind::Ref,
+            ty::Adt(..) => SigTyKind::Adt,
+            _ => SigTyKind::Other,
+        };
+
+        let mut adts = FxIndexSet::default();
+        for arg in ty.walk() {
+            if let Some(ty) = arg.as_type()
+                && let ty::Adt(def, _) = ty.kind()
+            {
+                adts.insert(*def);
+            }
+        }
+        let adts = adts
+            .into_iter()
+            .map(|def| {
+                let name = utils::name_internal(def.did(), tcx);
+                let stable_id = utils::stable_id_internal(def.did(), tcx);
+                AdtLink {
+                    name,
+                    stable_id,
+                    key: None,
+                }
+            })
+            .collect();
+
+        SigTy {
+            ty: with_no_trimmed_paths!(ty.to_string()),
+            kind,
+            adts,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::{
+    Writer,
+    adt::Access,
+    caller::{CalleeInfo, OutputPath, def_path, output_callee},
+    fn_::{Documentation, Mir, Source},
+    utils::Meta,
+};
+use crate::{
+    FxIndexMap,
+    info_mod::Navigation,
+    info_static::{StaticInfo, StaticKind},
+};
+use rustc_middle::ty::TyCtxt;
+use rustc_public::{CrateDef, CrateItem};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Static {
+    #[serde(skip)]
+    pub item: CrateItem,
+    #[serde(flatten)]
+    pub meta: Meta,
+    pub kind: StaticKind,
+    /// Direct callees in the initializer, keyed by stable id.
+    pub callees: FxIndexMap<String, CalleeInfo>,
+    /// Functions that read or write the item.
+    pub access: Access,
+    pub path: OutputPath,
+}
+
+impl Static {
+    pub fn new(item: CrateItem, info: &StaticInfo, tcx: TyCtxt, navi: &Navigation) -> Self {
+        Static {
+            item,
+            meta: Meta::new(item, tcx),
+            kind: info.kind,
+            callees: output_callee(&info.init, tcx),
+            access: Access::new(&info.access, tcx),
+            path: def_path(item.def_id(), tcx, navi),
+        }
+    }
+
+    /// Write the item info to `static/`, and src, mir, doc to the item directory.
+    pub fn dump(&self, writer: &Writer, tcx: TyCtxt) {
+        let key = &self.meta.storage_key();
+        writer.dump_json("static", key, self);
+
+        writer.dump_json(key, "doc", &Documentation::new(self.item, tcx));
+        if let Some(body) = self.item.body() {
+            writer.dump_json(key, "mir", &Mir::new(self.item, &body, tcx));
+            writer.dump_json(key, "src", &Source::new(self.item, tcx));
+        }
+    }
+}
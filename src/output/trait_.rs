@@ -0,0 +1,143 @@
+use super::{
+    caller::{OutputPath, def_path},
+    utils::{self, Meta},
+};
+use crate::{FxIndexMap, get_tags, info_mod::Navigation, info_trait::TraitInfo};
+use rustc_middle::ty::{TyCtxt, print::with_no_trimmed_paths};
+use rustc_public::{
+    CrateDef,
+    rustc_internal::internal,
+    ty::{AdtKind, ImplDef, RigidTy, TraitDef, Ty, TyKind},
+};
+use safety_parser::json::OutputProperties;
+use serde::Serialize;
+
+/// Data written to `traits.json`.
+#[derive(Serialize)]
+pub struct Traits {
+    pub traits: Vec<UnsafeTrait>,
+}
+
+impl Traits {
+    pub fn new(map: &FxIndexMap<TraitDef, TraitInfo>, tcx: TyCtxt, navi: &Navigation) -> Self {
+        let traits = map
+            .iter()
+            .map(|(&trait_def, info)| UnsafeTrait::new(trait_def, info, tcx, navi))
+            .collect();
+        Traits { traits }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UnsafeTrait {
+    #[serde(skip)]
+    pub trait_def: TraitDef,
+    #[serde(flatten)]
+    pub meta: Meta,
+    /// The `# Safety` section in the doc.
+    pub safety_doc: String,
+    pub tags: Vec<OutputProperties>,
+    pub path: OutputPath,
+    pub impls: Vec<UnsafeImpl>,
+}
+
+impl UnsafeTrait {
+    fn new(trait_def: TraitDef, info: &TraitInfo, tcx: TyCtxt, navi: &Navigation) -> Self {
+        UnsafeTrait {
+            trait_def,
+            meta: Meta::new(trait_def, tcx),
+            safety_doc: safety_doc(&utils::doc(trait_def, tcx)),
+            tags: get_tags(trait_def)
+                .into_iter()
+                .map(OutputProperties::new)
+                .collect(),
+            path: def_path(trait_def.def_id(), tcx, navi),
+            impls: info
+                .impls
+                .iter()
+                .map(|&impl_def| UnsafeImpl::new(impl_def, tcx, navi))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UnsafeImpl {
+    #[serde(flatten)]
+    pub meta: Meta,
+    pub self_ty: String,
+    /// Set if the self type is an adt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adt: Option<String>,
+    /// Field name to field type. Enum fields are named as `Variant.field`.
+    pub fields: FxIndexMap<String, String>,
+    pub path: OutputPath,
+}
+
+impl UnsafeImpl {
+    fn new(impl_def: ImplDef, tcx: TyCtxt, navi: &Navigation) -> Self {
+        let self_ty = impl_def.trait_impl().value.self_ty();
+
+        let mut adt = None;
+        let mut fields = FxIndexMap::default();
+        if let TyKind::RigidTy(RigidTy::Adt(adt_def, args)) = self_ty.kind() {
+            adt = Some(utils::name(adt_def, tcx));
+            let is_enum = matches!(adt_def.kind(), AdtKind::Enum);
+            for variant in adt_def.variants_iter() {
+                for field in variant.fields() {
+                    let name = if is_enum {
+                        format!("{}.{}", variant.name(), field.name)
+                    } else {
+                        field.name.clone()
+                    };
+                    fields.insert(name, ty_str(field.ty_with_args(&args), tcx));
+                }
+            }
+        }
+
+        UnsafeImpl {
+            meta: Meta::new(impl_def, tcx),
+            self_ty: ty_str(self_ty, tcx),
+            adt,
+            fields,
+            path: def_path(impl_def.def_id(), tcx, navi),
+        }
+    }
+}
+
+fn ty_str(ty: Ty, tcx: TyCtxt) -> String {
+    with_no_trimmed_paths!(internal(tcx, ty).to_string())
+}
+
+/// Extract the `# Safety` section from the doc, excluding the heading.
+fn safety_doc(doc: &str) -> String {
+    let mut buf = String::new();
+    let mut level = None;
+    // The opening fence of the code block we're in, whose `#` lines are not headings.
+    let mut fence: Option<&str> = None;
+    for line in doc.lines() {
+        let heading = line.trim_start();
+        match fence {
+            Some(open) if heading.starts_with(open) => fence = None,
+            Some(_) => (),
+            None if heading.starts_with("```") => fence = Some("```"),
+            None if heading.starts_with("~~~") => fence = Some("~~~"),
+            None => (),
+        }
+        let hashes = heading.bytes().take_while(|b| *b == b'#').count();
+        let is_heading = fence.is_none() && hashes > 0 && heading[hashes..].starts_with(' ');
+        match level {
+            None if is_heading && heading[hashes..].trim().eq_ignore_ascii_case("safety") => {
+                level = Some(hashes);
+            }
+            // The section ends at the next heading of the same or higher level.
+            Some(l) if is_heading && hashes <= l => break,
+            Some(_) => {
+                buf.push_str(line);
+                buf.push('\n');
+            }
+            None => (),
+        }
+    }
+    buf.trim().to_owned()
+}
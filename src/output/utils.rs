@@ -1,12 +1,21 @@
 extern crate rustc_hir_pretty;
 use itertools::Itertools;
-use rustc_hir::{Attribute, attrs::AttributeKind, def_id::DefId as IDefId};
-use rustc_middle::ty::TyCtxt;
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_hir::{
+    Attribute,
+    attrs::{AttributeKind, InlineAttr},
+    def::DefKind,
+    def_id::DefId as IDefId,
+};
+use rustc_middle::{
+    middle::codegen_fn_attrs::CodegenFnAttrFlags,
+    ty::{TyCtxt, Visibility, print::with_no_trimmed_paths},
+};
 use rustc_public::{
     CrateDef,
     mir::Safety,
     rustc_internal::internal,
-    ty::{FnDef, Span},
+    ty::{FnDef, GenericArgs, Span},
 };
 use serde::Serialize;
 
@@ -16,6 +25,7 @@ use crate::info_mod::crate_name;
 pub struct Meta {
     pub name: String,
     pub span: String,
+    pub stable_id: String,
 }
 
 impl Meta {
@@ -23,8 +33,149 @@ impl Meta {
         Meta {
             name: name(item, tcx),
             span: span(item, tcx),
+            stable_id: stable_id(item, tcx),
         }
     }
+
+    /// The directory name of the item in the output directory.
+    pub fn storage_key(&self) -> String {
+        storage_key(&self.name, &self.stable_id)
+    }
+}
+
+/// Function attributes for audits, shared by `caller.json` and `doc.json`.
+#[derive(Debug, Serialize)]
+pub struct FnMeta {
+    /// `pub`, `pub(crate)`, `pub(in path)`, or `private`.
+    pub visibility: String,
+    pub const_fn: bool,
+    pub async_fn: bool,
+    /// The ABI without quotes, like `Rust` or `C`.
+    pub abi: String,
+    /// `hint` for a plain `#[inline]`, `always`, `never`, or `force`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inline: Option<&'static str>,
+    /// Features enabled by `#[target_feature]`, including implied ones.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub target_features: Vec<String>,
+    pub no_mangle: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_name: Option<String>,
+    pub track_caller: bool,
+    /// The trait if the function is a method in a trait impl, e.g. `core::clone::Clone`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trait_impl: Option<String>,
+}
+
+impl FnMeta {
+    pub fn new(fn_def: FnDef, tcx: TyCtxt) -> Self {
+        let did = did(fn_def, tcx);
+        let attrs = tcx.codegen_fn_attrs(did);
+        let inline = match attrs.inline {
+            InlineAttr::None => None,
+            InlineAttr::Hint => Some("hint"),
+            InlineAttr::Always => Some("always"),
+            InlineAttr::Never => Some("never"),
+            InlineAttr::Force { .. } => Some("force"),
+        };
+        // `symbol_name` is from `#[link_name]` for foreign functions.
+        let export_name = attrs.symbol_name.filter(|_| !tcx.is_foreign_item(did));
+        let trait_impl = tcx.trait_impl_of_assoc(did).map(|imp| {
+            let trait_did = tcx.impl_trait_ref(imp).skip_binder().def_id;
+            name_internal(trait_did, tcx)
+        });
+        FnMeta {
+            visibility: visibility(did, tcx),
+            const_fn: tcx.is_const_fn(did),
+            async_fn: tcx.asyncness(did).is_async(),
+            abi: tcx.fn_sig(did).skip_binder().abi().as_str().to_owned(),
+            inline,
+            target_features: attrs
+                .target_features
+                .iter()
+                .map(|f| f.name.to_string())
+                .collect(),
+            no_mangle: attrs.flags.contains(CodegenFnAttrFlags::NO_MANGLE),
+            export_name: export_name.map(|name| name.to_string()),
+            track_caller: attrs.flags.contains(CodegenFnAttrFlags::TRACK_CALLER),
+            trait_impl,
+        }
+    }
+}
+
+fn visibility(did: IDefId, tcx: TyCtxt) -> String {
+    let Visibility::Restricted(module) = tcx.visibility(did) else {
+        return "pub".to_owned();
+    };
+    // The nearest module of the item.
+    let mut parent = tcx.parent(did);
+    while tcx.def_kind(parent) != DefKind::Mod {
+        parent = tcx.parent(parent);
+    }
+    if module == parent {
+        "private".to_owned()
+    } else if module.is_crate_root() {
+        "pub(crate)".to_owned()
+    } else {
+        let path = with_no_trimmed_paths!(tcx.def_path_str(module));
+        let krate = if module.is_local() { "crate::" } else { "" };
+        format!("pub(in {krate}{path})")
+    }
+}
+
+/// The id of an item that is shared across output files, which is the `DefPathHash` in 32 hex
+/// digits. Unlike the name, it is unique and doesn't change with formatting, but it changes
+/// with `-C metadata` of the crate, e.g. when the crate version is bumped.
+pub fn stable_id<T: CrateDef>(item: T, tcx: TyCtxt) -> String {
+    stable_id_internal(did(item, tcx), tcx)
+}
+
+pub fn stable_id_internal(did: IDefId, tcx: TyCtxt) -> String {
+    hex(tcx.def_path_hash(did).0)
+}
+
+/// The file or directory name to store outputs of an item, which is unique and portable unlike
+/// the name, e.g. `poc.SecretRegion.from~142372962e6f301f` for `poc::SecretRegion::from`.
+///
+/// `::` is replaced by `.`, and other characters except ASCII alphanumerics and `_` by `_`, so
+/// the readable part may collide, and is followed by the local part of the stable id.
+/// `keys.json` maps names to keys.
+pub fn storage_key(name: &str, stable_id: &str) -> String {
+    const MAX_LEN: usize = 100;
+
+    let mut key = String::with_capacity(MAX_LEN + 17);
+    for c in name.replace("::", ".").chars() {
+        if key.len() == MAX_LEN {
+            break;
+        }
+        key.push(if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            c
+        } else {
+            '_'
+        });
+    }
+    // The stable id is the crate part followed by the local part, both in 16 hex digits.
+    let local = stable_id.get(16..).unwrap_or(stable_id);
+    format!("{key}~{local}")
+}
+
+/// Tags are not items, so the id is the hash of the tag name.
+pub fn tag_stable_id(tag: &str) -> String {
+    use rustc_data_structures::stable_hasher::StableHasher;
+    use std::hash::Hash;
+
+    let mut hasher = StableHasher::new();
+    "tag".hash(&mut hasher);
+    tag.hash(&mut hasher);
+    hex(hasher.finish())
+}
+
+fn hex(fingerprint: Fingerprint) -> String {
+    fingerprint
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
 }
 
 pub fn name<T: CrateDef + Copy>(item: T, tcx: TyCtxt) -> String {
@@ -38,8 +189,34 @@ pub fn name<T: CrateDef + Copy>(item: T, tcx: TyCtxt) -> String {
     name
 }
 
+/// Like [`name`], but for internal `DefId`s.
+pub fn name_internal(did: IDefId, tcx: TyCtxt) -> String {
+    let name = with_no_trimmed_paths!(tcx.def_path_str(did));
+    if did.is_local() {
+        format!("{}::{name}", crate_name(tcx).as_str())
+    } else {
+        name
+    }
+}
+
+/// Like [`name`], but with generic arguments, e.g. `std::vec::Vec::<u8>::set_len`.
+pub fn name_with_args<T: CrateDef + Copy>(item: T, args: &GenericArgs, tcx: TyCtxt) -> String {
+    let did = did(item, tcx);
+    let args = internal(tcx, args);
+    let name = with_no_trimmed_paths!(tcx.def_path_str_with_args(did, args));
+    if did.is_local() {
+        format!("{}::{name}", crate_name(tcx).as_str())
+    } else {
+        name
+    }
+}
+
 pub fn span<T: CrateDef>(item: T, tcx: TyCtxt) -> String {
-    let span = internal(tcx, item.span());
+    span_string(item.span(), tcx)
+}
+
+pub fn span_string(span: Span, tcx: TyCtxt) -> String {
+    let span = internal(tcx, span);
     let src_map = tcx.sess.source_map();
     // --remap-path-prefix
     src_map.span_to_string(span, rustc_span::FileNameDisplayPreference::Remapped)
@@ -136,3 +313,70 @@ pub fn doc_internal(did: IDefId, tcx: TyCtxt) -> String {
 pub fn is_safe(fn_def: FnDef) -> bool {
     matches!(fn_def.fn_sig().value.safety, Safety::Safe)
 }
+
+/// Names of safety tags on the item in order of appearance. Tags in `any` are flattened.
+pub fn tag_names(item: impl CrateDef) -> Vec<String> {
+    let mut names = crate::FxIndexSet::default();
+    for props in crate::get_tags(item) {
+        for tag in safety_parser::json::OutputProperties::new(props).tags {
+            if let Some(any_args) = tag.sp.args_in_any_tag() {
+                for arg in any_args {
+                    for tag in arg.tags {
+                        names.insert(tag.tag.name().to_owned());
+                    }
+                }
+            } else {
+                names.insert(tag.sp.tag.name().to_owned());
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// Safety tags on the item with their arguments in order of appearance, like `ValidPtr` with
+/// `["p"]` for `ValidPtr(p)`. Tags in `any` are skipped, because their arguments are tags.
+pub fn tag_args(item: impl CrateDef) -> Vec<(String, Vec<String>)> {
+    let mut v = Vec::new();
+    for props in crate::get_tags(item) {
+        for tag in safety_parser::json::OutputProperties::new(props).tags {
+            if tag.sp.args_in_any_tag().is_some() {
+                continue;
+            }
+            // Arguments are serialized as strings like what the UI reads.
+            let sp = serde_json::to_value(&tag.sp).unwrap_or_default();
+            let args = sp["args"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|arg| arg.as_str().map(String::from))
+                .collect();
+            v.push((tag.sp.tag.name().to_owned(), args));
+        }
+    }
+    v
+}
+
+/// The remapped file name and the line and column range of a span, like in [`span`].
+pub struct SpanLocation {
+    pub file: String,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+pub fn span_location(span: rustc_span::Span, tcx: TyCtxt) -> Option<SpanLocation> {
+    let src_map = tcx.sess.source_map();
+    let (file, start_line, start_col, end_line, end_col) = src_map.span_to_location_info(span);
+    let file = file?
+        .name
+        .display(rustc_span::FileNameDisplayPreference::Remapped)
+        .to_string();
+    Some(SpanLocation {
+        file,
+        start_line,
+        start_col,
+        end_line,
+        end_col,
+    })
+}
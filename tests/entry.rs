@@ -2,10 +2,19 @@
 
 extern crate compiletest_rs as compiletest;
 
-use std::{env, path::PathBuf, sync::LazyLock};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::LazyLock,
+};
+
+fn is_bless() -> bool {
+    env::var("BLESS").is_ok_and(|x| !x.trim().is_empty())
+}
 
 fn run_mode(dir: &str) {
-    let bless = env::var("BLESS").is_ok_and(|x| !x.trim().is_empty());
+    let bless = is_bless();
     let dir = &format!("tests/{dir}");
 
     let config = compiletest::Config {
@@ -35,7 +44,15 @@ static PROFILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
 });
 
 fn flag_remap_path_prefix() -> String {
-    let output = std::process::Command::new("rustc")
+    // sysroot should be /home/gh-zjp-CN/.rustup/toolchains/nightly-2025-12-06-aarch64-unknown-linux-gnu
+    // Replace           /home/gh-zjp-CN/.rustup/toolchains/nightly-2025-12-06-aarch64-unknown-linux-gnu/lib/rustlib/src/rust/library
+    // by $SYSROOT.
+    let sysroot = sysroot();
+    format!("--remap-path-prefix={sysroot}/lib/rustlib/src/rust/library=$SYSROOT")
+}
+
+fn sysroot() -> String {
+    let output = Command::new("rustc")
         .args(["--print=sysroot"])
         .output()
         .unwrap();
@@ -44,14 +61,404 @@ fn flag_remap_path_prefix() -> String {
         let stderr = String::from_utf8(output.stderr).unwrap();
         panic!("`rustc --print=sysroot` fails\nstderr={stderr}\nstdout={stdout}");
     }
-    // sysroot should be /home/gh-zjp-CN/.rustup/toolchains/nightly-2025-12-06-aarch64-unknown-linux-gnu
-    // Replace           /home/gh-zjp-CN/.rustup/toolchains/nightly-2025-12-06-aarch64-unknown-linux-gnu/lib/rustlib/src/rust/library
-    // by $SYSROOT.
-    let sysroot = stdout.trim();
-    format!("--remap-path-prefix={sysroot}/lib/rustlib/src/rust/library=$SYSROOT")
+    stdout.trim().to_owned()
 }
 
 #[test]
 fn compile_test() {
     run_mode("pass");
 }
+
+/// Run the driver on a file in `tests/pass`, and return stdout.
+fn run_driver(file: &str, out_dir: &str, envs: &[(&str, &str)]) -> String {
+    let mut cmd = driver(file, out_dir, envs);
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        panic!("{cmd:?} fails\nstderr={stderr}\nstdout={stdout}");
+    }
+    stdout
+}
+
+/// The driver command on a file in `tests/pass`.
+fn driver(file: &str, out_dir: &str, envs: &[(&str, &str)]) -> Command {
+    let src_dir = env::current_dir().unwrap().join("tests/pass");
+    let mut cmd = Command::new(PROFILE_PATH.join(RUSTC_DRIVER));
+    cmd.arg(src_dir.join(file))
+        .args(["--crate-type=lib", "--edition=2015", "--emit=metadata"])
+        .arg(flag_remap_path_prefix())
+        .arg(format!("--remap-path-prefix={}=$DIR", src_dir.display()))
+        .arg(format!(
+            "--out-dir={}",
+            PROFILE_PATH.join(out_dir).display()
+        ))
+        .env_remove("UPG_DIR")
+        .envs(envs.iter().copied());
+    cmd
+}
+
+/// Run the driver on a file in `tests/pass` with a fresh `UPG_DIR` under `out_dir`, and return
+/// the output directory of the crate.
+fn run_upg(file: &str, out_dir: &str) -> PathBuf {
+    let upg_dir = PROFILE_PATH.join(out_dir);
+    _ = fs::remove_dir_all(&upg_dir);
+    run_driver(file, out_dir, &[("UPG_DIR", upg_dir.to_str().unwrap())]);
+    let crate_name = file.trim_end_matches(".rs").replace('-', "_");
+    upg_dir.join(crate_name)
+}
+
+fn read_json(path: impl AsRef<Path>) -> serde_json::Value {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("{path:?}: {err}"));
+    serde_json::from_str(&text).unwrap()
+}
+
+/// The node id of the item with the name in `navi/tree.json`. Items sharing the name are not
+/// expected.
+fn navi_node_id(tree: &serde_json::Value, name: &str) -> Option<u64> {
+    let mut items = tree["items"].as_object().unwrap().values();
+    let item = items.find(|item| item["name"] == name)?;
+    item["id"].as_u64()
+}
+
+/// Ids of all nodes in the subtree of the navigation tree.
+fn node_ids(tree: &serde_json::Value) -> Vec<u64> {
+    let mut ids = vec![tree["node"]["id"].as_u64().unwrap()];
+    for sub in tree["sub"].as_array().unwrap() {
+        ids.extend(node_ids(sub));
+    }
+    ids
+}
+
+/// The SARIF report of `tests/pass/poc.rs` against `tests/sarif/poc.sarif`.
+#[test]
+fn sarif() {
+    let stdout = run_driver("poc.rs", "tests/sarif", &[("UPG_FORMAT", "sarif")]);
+    // Base uris are real paths on this machine.
+    let src_dir = env::current_dir().unwrap().join("tests/pass");
+    let stdout = stdout
+        .replace(sysroot().as_str(), "{SYSROOT}")
+        .replace(src_dir.to_str().unwrap(), "{DIR}");
+
+    // Every rule has a result.
+    let sarif: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let run = &sarif["runs"][0];
+    let results = run["results"].as_array().unwrap();
+    for rule in run["tool"]["driver"]["rules"].as_array().unwrap() {
+        let id = &rule["id"];
+        let fired = results.iter().any(|result| result["ruleId"] == *id);
+        assert!(fired, "{id} has no result");
+    }
+
+    let expected = "tests/sarif/poc.sarif";
+    if is_bless() {
+        fs::write(expected, &stdout).unwrap();
+    } else {
+        let expected = fs::read_to_string(expected).unwrap();
+        assert!(expected == stdout, "SARIF of poc.rs differs:\n{stdout}");
+    }
+}
+
+/// Functions sharing a type or a name are written to distinct and portable directories.
+#[test]
+fn storage_keys() {
+    let dir = run_upg("from-impls.rs", "tests/storage");
+    let keys = read_json(dir.join("keys.json"));
+    let ids = read_json(dir.join("ids.json"));
+
+    let mut fn_keys = Vec::new();
+    for (stable_id, entry) in ids.as_object().unwrap() {
+        let name = entry["name"].as_str().unwrap();
+        let Some(key) = entry["key"].as_str() else {
+            continue;
+        };
+        assert!(
+            key.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_.~".contains(c)),
+            "{key} of {name} is not portable"
+        );
+        let is_local_fn = entry["kind"] == "Fn" && name.starts_with("from_impls::");
+        if is_local_fn {
+            let caller = read_json(dir.join(key).join("caller.json"));
+            assert_eq!(
+                caller["stable_id"],
+                stable_id.as_str(),
+                "{key} is overwritten"
+            );
+            fn_keys.push((name, key));
+        }
+    }
+
+    let from_impls = fn_keys.iter().filter(|(name, _)| name.ends_with(">::from"));
+    assert_eq!(from_impls.count(), 3, "{fn_keys:?}");
+    let helpers = fn_keys
+        .iter()
+        .filter(|(name, _)| name.ends_with("::helper"));
+    assert_eq!(helpers.count(), 2, "{fn_keys:?}");
+    let mut distinct: Vec<_> = fn_keys.iter().map(|(_, key)| key.to_lowercase()).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert_eq!(distinct.len(), fn_keys.len(), "{fn_keys:?}");
+
+    // Callees sharing a name are all listed, and each has its own node in the navigation tree.
+    let helpers: Vec<_> = ids
+        .as_object()
+        .unwrap()
+        .iter()
+        .filter(|(_, entry)| entry["name"] == "from_impls::same_names::helper")
+        .map(|(stable_id, _)| stable_id.as_str())
+        .collect();
+    assert_eq!(helpers.len(), 2, "{helpers:?}");
+    let (_, same_names) = fn_keys
+        .iter()
+        .find(|(name, _)| *name == "from_impls::same_names")
+        .unwrap();
+    let caller = read_json(dir.join(same_names).join("caller.json"));
+    let tree = read_json(dir.join("navi").join("tree.json"));
+    let mut node_ids = Vec::new();
+    for stable_id in &helpers {
+        let callee = &caller["callees"][stable_id];
+        assert_eq!(
+            callee["name"], "from_impls::same_names::helper",
+            "{stable_id}"
+        );
+        let item = &tree["items"][stable_id];
+        assert_eq!(
+            item["name"], "from_impls::same_names::helper",
+            "{stable_id}"
+        );
+        node_ids.push(item["id"].as_u64().unwrap());
+    }
+    assert_ne!(node_ids[0], node_ids[1], "helpers share a node");
+
+    // Names map to keys of all items sharing them.
+    for (name, len) in [
+        ("from_impls::SecretRegion::from", 1),
+        ("from_impls::same_names::helper", 2),
+    ] {
+        let keys = keys[name].as_array().unwrap();
+        assert_eq!(keys.len(), len, "{name}: {keys:?}");
+        for key in keys {
+            assert!(fn_keys.contains(&(name, key.as_str().unwrap())));
+        }
+    }
+}
+
+/// Every local function in `ids.json` of `tests/pass/navi-coverage.rs` has a node in the
+/// navigation tree.
+#[test]
+fn navi_coverage() {
+    let dir = run_upg("navi-coverage.rs", "tests/navi");
+    let ids = read_json(dir.join("ids.json"));
+    let tree = read_json(dir.join("navi").join("tree.json"));
+
+    let local_fns: Vec<_> = ids
+        .as_object()
+        .unwrap()
+        .values()
+        .filter(|entry| entry["kind"] == "Fn" && entry["key"].is_string())
+        .filter_map(|entry| entry["name"].as_str())
+        .filter(|name| name.starts_with("navi_coverage::"))
+        .collect();
+    let missing: Vec<_> = local_fns
+        .iter()
+        .filter(|name| navi_node_id(&tree, name).is_none())
+        .collect();
+    assert!(missing.is_empty(), "{missing:?} have no nodes");
+
+    for name in [
+        "navi_coverage::Buffer::is_empty",
+        "navi_coverage::outer::nested::nested_twice",
+        "navi_coverage::two",
+        "navi_coverage::ffi::abs",
+    ] {
+        assert!(local_fns.contains(&name), "{name} is not analyzed");
+    }
+}
+
+/// Impls of `tests/pass/odd-impls.rs` for references, type parameters, primitives, and foreign
+/// types are placed in the navigation tree without diagnostics.
+#[test]
+fn odd_impls() {
+    let dir = run_upg("odd-impls.rs", "tests/odd-impls");
+    let diagnostics = read_json(dir.join("diagnostics.json"));
+    assert_eq!(diagnostics, serde_json::json!([]));
+
+    let tree = read_json(dir.join("navi").join("tree.json"));
+    for name in [
+        "odd_impls::<&Adt as Len>::len",
+        "odd_impls::<T as Describe>::describe",
+        "odd_impls::<u8 as Len>::len",
+        "odd_impls::<[u8] as Len>::len",
+        "odd_impls::<(Adt, Adt) as Len>::len",
+        "odd_impls::<impl std::convert::From<Adt> for usize>::from",
+    ] {
+        assert!(navi_node_id(&tree, name).is_some(), "{name} has no node");
+    }
+
+    // The subtree at the path of `(kind, name)` from the root.
+    let subtree = |path: &[(&str, &str)]| -> Vec<u64> {
+        let mut tree = &tree["tree"];
+        for (kind, name) in path {
+            let subtrees = tree["sub"].as_array().unwrap();
+            let node =
+                |t: &&serde_json::Value| t["node"]["kind"] == *kind && t["node"]["name"] == *name;
+            tree = subtrees
+                .iter()
+                .find(node)
+                .unwrap_or_else(|| panic!("{path:?} not found"));
+        }
+        node_ids(tree)
+    };
+    let is_under = |name: &str, path: &[(&str, &str)]| {
+        let id = navi_node_id(&tree, name).unwrap();
+        assert!(subtree(path).contains(&id), "{name} is not under {path:?}");
+    };
+
+    let name = "odd_impls::<impl std::convert::From<Adt> for std::vec::Vec<u8>>::from";
+    is_under(name, &[("Mod", "__phony")]);
+
+    // Self types are structured, and put under the adt they wrap.
+    let adt = ("Struct", "Adt");
+    is_under("odd_impls::<&Adt as Len>::len", &[adt, ("Ref", "&Adt")]);
+    is_under(
+        "odd_impls::<(Adt, Adt) as Len>::len",
+        &[adt, ("Tuple", "(Adt, Adt)")],
+    );
+    is_under(
+        "odd_impls::<[Adt; 2] as Len>::len",
+        &[adt, ("Array", "[Adt; 2]")],
+    );
+    // Local adts are preferred over foreign ones wrapping them.
+    let option = "std::option::Option<Adt>";
+    is_under(
+        "odd_impls::<std::option::Option<Adt> as Len>::len",
+        &[adt, ("Ty", option)],
+    );
+    is_under(
+        "odd_impls::<&std::option::Option<Adt> as Len>::len",
+        &[adt, ("Ref", &format!("&{option}"))],
+    );
+    let primitive = ("Mod", "__primitive");
+    is_under(
+        "odd_impls::<&[u8] as Len>::len",
+        &[primitive, ("Ref", "&[u8]")],
+    );
+    is_under(
+        "odd_impls::<*mut T as Len>::len",
+        &[primitive, ("Ptr", "*mut T")],
+    );
+    is_under(
+        "odd_impls::<[u8] as Len>::len",
+        &[primitive, ("Slice", "[u8]")],
+    );
+    is_under(
+        "odd_impls::<T as Describe>::describe",
+        &[primitive, ("Param", "T")],
+    );
+    is_under("odd_impls::<u8 as Len>::len", &[primitive, ("Ty", "u8")]);
+}
+
+/// Items with paths outside of the crate root are put under `__phony` with diagnostics, while
+/// impls of foreign traits for foreign types are put there without diagnostics.
+#[test]
+fn navi_diagnostics() {
+    let dir = run_upg("navi-diagnostics.rs", "tests/navi-diagnostics");
+    let diagnostics = read_json(dir.join("diagnostics.json"));
+    let diagnostics: Vec<_> = diagnostics
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| (d["kind"].as_str().unwrap(), d["item"].as_str().unwrap()))
+        .collect();
+    let odd_len = "navi_diagnostics::<impl std::ffi::CStr>::odd_len";
+    assert_eq!(diagnostics, [("NonLocalRoot", odd_len)]);
+
+    let tree = read_json(dir.join("navi").join("tree.json"));
+    let phony = tree["tree"]["sub"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["node"]["name"] == "__phony")
+        .expect("no __phony node");
+    let ids = node_ids(phony);
+    for name in [
+        odd_len,
+        "navi_diagnostics::<impl std::convert::From<Adt> for std::vec::Vec<u8>>::from",
+    ] {
+        let id = navi_node_id(&tree, name).unwrap();
+        assert!(ids.contains(&id), "{name} is not under __phony");
+    }
+}
+
+/// New findings fail the build until they are blessed into the baseline.
+#[test]
+fn check_baseline() {
+    let baseline = PROFILE_PATH.join("tests/check");
+    _ = fs::remove_dir_all(&baseline);
+    fs::create_dir_all(&baseline).unwrap();
+    let envs = [("UPG_BASELINE", baseline.to_str().unwrap())];
+
+    let output = driver("check-baseline.rs", "tests/check", &envs)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert!(stderr.contains("error: 10 new unsafe findings"), "{stderr}");
+
+    let bless = [envs[0], ("UPG_BLESS", "1")];
+    run_driver("check-baseline.rs", "tests/check", &bless);
+    let json = read_json(baseline.join("check_baseline.json"));
+    // Entries are keyed by stable ids and map to names.
+    let blocks = json["unsafe_blocks"].as_object().unwrap();
+    let block_count = |name: &str| {
+        let mut named = blocks.values().filter(|b| b["name"] == name);
+        named.next().map(|b| b["count"].clone())
+    };
+    assert_eq!(block_count("check_baseline::new_findings"), Some(1.into()));
+    // The safety comment is above the `let` statement of the block.
+    assert_eq!(block_count("check_baseline::commented_statement"), None);
+    let writes = json["raw_field_writes"].as_object().unwrap();
+    let (id, _) = writes
+        .iter()
+        .find(|(_, name)| *name == "check_baseline::allowed_write -> check_baseline::Node.len")
+        .unwrap_or_else(|| panic!("{writes:?}"));
+    assert!(!id.contains("::") && id.ends_with(".len"), "{id}");
+
+    run_driver("check-baseline.rs", "tests/check", &envs);
+}
+
+/// Lint levels are controlled by flags like other rustc lints.
+#[test]
+fn lint_levels() {
+    let envs = [("UPG_LINT", "1")];
+    let run = |args: &[&str]| {
+        let output = driver("lints.rs", "tests/lints", &envs)
+            .args(args)
+            .output()
+            .unwrap();
+        (
+            output.status.success(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    // `denied` has `#[deny]`, so lints are capped to pass.
+    let (success, stderr) = run(&["--cap-lints=warn"]);
+    assert!(success, "{stderr}");
+    assert!(stderr.contains("warning: unsafe block has no"), "{stderr}");
+
+    let (success, stderr) = run(&["--cap-lints=allow"]);
+    assert!(success, "{stderr}");
+    assert!(!stderr.contains("rapx::"), "{stderr}");
+
+    let (success, stderr) = run(&["--cap-lints=warn", "-Arapx::missing_safety_tag"]);
+    assert!(success, "{stderr}");
+    let missing = "unsafe function `lints::untagged` has no safety tags";
+    assert!(!stderr.contains(missing), "{stderr}");
+
+    let (success, stderr) = run(&["-Drapx::missing_safety_tag"]);
+    assert!(!success);
+    assert!(stderr.contains(&format!("error: {missing}")), "{stderr}");
+}
@@ -0,0 +1,31 @@
+//! Each call site with its basic block, unsafe block, and argument sources.
+#![allow(dead_code)]
+
+pub struct Buf {
+    ptr: *mut u8,
+    len: usize,
+}
+
+static mut COUNT: usize = 0;
+
+unsafe fn write(ptr: *mut u8, offset: usize, value: u8) {
+    unsafe { *ptr.add(offset) = value };
+}
+
+fn bump(count: *mut usize) {
+    let _ = count;
+}
+
+impl Buf {
+    pub fn last(&self) -> usize {
+        self.len - 1
+    }
+
+    pub fn fill(&mut self, value: u8) {
+        // Called twice: once outside and once inside an unsafe block.
+        let offset = self.last();
+        unsafe { write(self.ptr, offset, value) };
+        unsafe { write(self.ptr, 0, 0) };
+        bump(&raw mut COUNT);
+    }
+}
@@ -0,0 +1,55 @@
+// rustc-env:UPG_BASELINE=tests/check
+// rustc-env:UPG_ALLOWLIST=tests/check/allowlist.txt
+#![feature(register_tool)]
+#![register_tool(rapx)]
+
+pub struct Node {
+    pub len: usize,
+}
+
+pub unsafe fn untagged() {}
+
+// Tagged functions are not checked.
+#[rapx::requires(ValidPtr(node))]
+pub unsafe fn set_len(node: *mut Node, len: usize) {
+    (*node).len = len;
+}
+
+// In the baseline.
+pub fn old_call() {
+    unsafe { untagged() }
+}
+
+// Allowed by the function name.
+pub fn allowed_by_name() {
+    unsafe { untagged() }
+}
+
+// The write is allowed by the finding, but the unsafe block is not.
+pub fn allowed_write(node: *mut Node) {
+    unsafe { (*node).len = 1 } //~ ERROR unsafe block
+}
+
+// Calls and writes in a commented block are not reported, and others are reported at each
+// call or write.
+pub fn new_findings(node: *mut Node) {
+    // SAFETY: nothing to uphold.
+    unsafe {
+        untagged();
+        (*node).len = 0;
+    }
+    unsafe {
+        //~^ ERROR unsafe block
+        untagged(); //~ ERROR unsafe call
+        (*node).len = 2; //~ ERROR field write
+        untagged(); //~ ERROR unsafe call
+    }
+}
+
+// The comment is above the statement containing the block.
+pub fn commented_statement(node: *mut Node) -> usize {
+    // SAFETY: nothing to uphold.
+    let len =
+        unsafe { untagged(); (*node).len };
+    len
+}
@@ -0,0 +1,8 @@
+// rustc-env:UPG_BASELINE=tests/check
+// The baseline is malformed, so the check fails before the analysis.
+
+pub unsafe fn untagged() {}
+
+pub fn call() {
+    unsafe { untagged() }
+}
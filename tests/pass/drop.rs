@@ -0,0 +1,37 @@
+pub struct RawBuf {
+    ptr: *mut u8,
+    cap: usize,
+}
+
+impl Drop for RawBuf {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, self.cap) }
+    }
+}
+
+unsafe fn dealloc(_ptr: *mut u8, _cap: usize) {}
+
+pub struct Wrapper {
+    buf: RawBuf,
+    name: String,
+}
+
+pub fn drop_raw(buf: RawBuf) {
+    let _ = buf.cap;
+}
+
+pub fn drop_wrapper(w: Wrapper) -> usize {
+    w.name.len()
+}
+
+pub fn drop_generic<T>(_t: T) {}
+
+pub fn explicit(buf: RawBuf) {
+    drop(buf);
+}
+
+/// `drop_in_place::<Wrapper>` is called explicitly and is also the drop glue of `w`.
+pub fn explicit_and_implicit(p: *mut Wrapper, w: Wrapper) {
+    unsafe { std::ptr::drop_in_place(p) };
+    let _ = w.name.len();
+}
@@ -0,0 +1,41 @@
+//! Visibility, qualifiers, ABI, and codegen attributes of functions.
+#![allow(dead_code)]
+
+pub mod inner {
+    pub fn public() {}
+
+    pub(crate) fn crate_visible() {}
+
+    pub mod deeper {
+        pub(super) fn parent_visible() {}
+    }
+
+    fn private() {}
+}
+
+pub const fn const_fn() -> u32 {
+    1
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn no_mangle_c() {}
+
+#[unsafe(export_name = "exported")]
+pub extern "C" fn export_name_c() {}
+
+#[inline(always)]
+pub fn inline_always() {}
+
+#[inline]
+pub fn inline_hint() {}
+
+#[track_caller]
+pub fn track_caller() {}
+
+pub struct Unit;
+
+impl Clone for Unit {
+    fn clone(&self) -> Self {
+        Unit
+    }
+}
@@ -0,0 +1,52 @@
+//! Several `From` impls on one type, along with an inherent `from`, are stored separately.
+#![allow(dead_code)]
+
+struct SecretRegion {
+    buffer: *mut u32,
+    len: usize,
+}
+
+impl From<Vec<u32>> for SecretRegion {
+    fn from(v: Vec<u32>) -> Self {
+        let (p, l, _c) = v.into_raw_parts();
+        SecretRegion { buffer: p, len: l }
+    }
+}
+
+impl From<&mut [u32]> for SecretRegion {
+    fn from(v: &mut [u32]) -> Self {
+        SecretRegion {
+            buffer: v.as_mut_ptr(),
+            len: v.len(),
+        }
+    }
+}
+
+impl From<(*mut u32, usize)> for SecretRegion {
+    fn from((buffer, len): (*mut u32, usize)) -> Self {
+        SecretRegion { buffer, len }
+    }
+}
+
+impl SecretRegion {
+    pub unsafe fn from(v: *mut u32, l: usize) -> Self {
+        SecretRegion { buffer: v, len: l }
+    }
+}
+
+/// Functions in different blocks have the same name.
+pub fn same_names() -> u32 {
+    let a = {
+        fn helper() -> u32 {
+            1
+        }
+        helper()
+    };
+    let b = {
+        fn helper() -> u32 {
+            2
+        }
+        helper()
+    };
+    a + b
+}
@@ -0,0 +1,39 @@
+//! Branches and assertions dominating unsafe calls.
+#![allow(dead_code)]
+
+pub struct Buf {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+}
+
+impl Buf {
+    pub fn get(&self, idx: usize) -> Option<u8> {
+        if idx < self.len {
+            Some(unsafe { *self.ptr.add(idx) })
+        } else {
+            None
+        }
+    }
+
+    pub fn push(&mut self, value: u8) {
+        if self.len == self.cap {
+            return;
+        }
+        unsafe { self.ptr.add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Both branches reach the call, so the `if` is not a guard.
+    pub fn clear(&mut self, shrink: bool) {
+        if shrink {
+            self.cap = self.len;
+        }
+        unsafe { self.ptr.write_bytes(0, self.len) };
+    }
+}
+
+pub fn first(v: &[*const u8], i: usize) -> u8 {
+    let ptr = v[i];
+    unsafe { ptr.read() }
+}
@@ -0,0 +1,16 @@
+#![feature(link_llvm_intrinsics, core_intrinsics)]
+#![allow(internal_features)]
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    #[link_name = "llvm.sqrt.f64"]
+    fn sqrt(x: f64) -> f64;
+}
+
+pub fn call_ffi(x: i32, y: f64) -> (i32, f64) {
+    unsafe { (abs(x), sqrt(y)) }
+}
+
+pub unsafe fn call_intrinsic(p: *const u8) -> u8 {
+    std::intrinsics::volatile_load(p)
+}
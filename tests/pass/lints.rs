@@ -0,0 +1,44 @@
+// rustc-env:UPG_LINT=1
+#![feature(register_tool)]
+#![register_tool(rapx)]
+
+#[rapx::requires(ValidPtr(ptr))]
+pub unsafe fn tagged(ptr: *const u8) -> u8 {
+    *ptr
+}
+
+pub unsafe fn untagged() {} //~ WARN has no safety tags
+
+#[allow(rapx::missing_safety_tag)]
+pub unsafe fn allowed() {}
+
+pub fn call_tagged(ptr: *const u8) -> u8 {
+    // SAFETY: the caller passes a valid pointer.
+    unsafe { tagged(ptr) }
+}
+
+pub fn call_untagged() {
+    unsafe { untagged() }
+    //~^ WARN calls unsafe function
+    //~| WARN unsafe block has no
+}
+
+#[allow(rapx::untagged_unsafe_callee, rapx::undocumented_unsafe_block)]
+pub fn call_allowed() {
+    unsafe { allowed() }
+}
+
+#[expect(rapx::missing_safety_tag)]
+pub unsafe fn expected() {}
+
+#[deny(rapx::undocumented_unsafe_block)]
+pub fn denied() {
+    unsafe { tagged(std::ptr::null()) }; //~ ERROR unsafe block has no
+}
+
+// The expectation is not fulfilled, because the block has a comment.
+#[expect(rapx::undocumented_unsafe_block)]
+pub fn unfulfilled() {
+    // SAFETY: null is never read.
+    unsafe { tagged(std::ptr::null()) };
+}
@@ -0,0 +1,65 @@
+//! Functions outside of plain modules and impls still have nodes in the navigation tree.
+#![allow(dead_code)]
+
+pub trait Buffer {
+    fn len(&self) -> usize;
+
+    /// A provided method.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A provided unsafe method.
+    unsafe fn get_unchecked(&self, idx: usize) -> u8 {
+        let _ = idx;
+        unreachable!()
+    }
+}
+
+pub struct Bytes(Vec<u8>);
+
+impl Buffer for Bytes {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+pub fn outer() -> usize {
+    fn nested() -> usize {
+        fn nested_twice() -> usize {
+            1
+        }
+        nested_twice()
+    }
+
+    struct Local;
+    impl Local {
+        fn method(&self) -> usize {
+            2
+        }
+    }
+
+    nested() + Local.method()
+}
+
+macro_rules! getter {
+    ($name:ident, $val:expr) => {
+        pub fn $name() -> u32 {
+            $val
+        }
+    };
+}
+
+getter!(one, 1);
+getter!(two, 2);
+
+mod ffi {
+    extern "C" {
+        pub fn abs(x: i32) -> i32;
+        pub static errno: i32;
+    }
+
+    pub fn call_abs(x: i32) -> i32 {
+        unsafe { abs(x) }
+    }
+}
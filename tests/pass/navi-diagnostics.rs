@@ -0,0 +1,26 @@
+//! Items placed under `__phony` in the navigation tree, with diagnostics for odd paths.
+#![feature(rustc_attrs)]
+#![allow(dead_code, internal_features)]
+
+use std::ffi::CStr;
+
+pub struct Adt {
+    len: usize,
+}
+
+/// Neither the Self type nor the trait is local, so the impl falls back to `__phony` without
+/// diagnostics.
+impl From<Adt> for Vec<u8> {
+    fn from(adt: Adt) -> Self {
+        vec![0; adt.len]
+    }
+}
+
+/// An inherent impl on a foreign type, allowed for types with incoherent inherent impls, has
+/// the path not starting from the crate root.
+impl CStr {
+    #[rustc_allow_incoherent_impl]
+    pub fn odd_len(&self) -> usize {
+        self.count_bytes()
+    }
+}
@@ -0,0 +1,93 @@
+//! Impls whose Self type is not a local adt are placed in the navigation tree without panics.
+#![allow(dead_code)]
+
+pub struct Adt {
+    len: usize,
+}
+
+pub trait Len {
+    fn len(&self) -> usize;
+}
+
+/// Self is a reference to a local adt.
+impl Len for &Adt {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// A blanket impl.
+pub trait Describe {
+    fn describe(&self) -> &'static str;
+}
+
+impl<T> Describe for T {
+    fn describe(&self) -> &'static str {
+        "anything"
+    }
+}
+
+/// Primitive impls of a local trait.
+impl Len for u8 {
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+impl Len for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+impl Len for &[u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+impl<T> Len for *mut T {
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+impl Len for [Adt; 2] {
+    fn len(&self) -> usize {
+        self[0].len + self[1].len
+    }
+}
+
+/// Impls on types wrapping an adt are put under the adt.
+impl Len for (Adt, Adt) {
+    fn len(&self) -> usize {
+        self.0.len + self.1.len
+    }
+}
+
+/// Foreign adts wrapping a local adt are put under the local one.
+impl Len for Option<Adt> {
+    fn len(&self) -> usize {
+        self.as_ref().map_or(0, |adt| adt.len)
+    }
+}
+
+impl Len for &Option<Adt> {
+    fn len(&self) -> usize {
+        (*self).len()
+    }
+}
+
+/// A primitive impl of a foreign trait.
+impl From<Adt> for usize {
+    fn from(adt: Adt) -> usize {
+        adt.len
+    }
+}
+
+/// Neither the trait nor Self is local, so the impl is under `__phony`.
+impl From<Adt> for Vec<u8> {
+    fn from(adt: Adt) -> Vec<u8> {
+        vec![0; adt.len]
+    }
+}
@@ -0,0 +1,29 @@
+// compile-flags: -Cpanic=abort
+//! Panic paths with `-Cpanic=abort`, where no call or drop has unwind edges.
+#![allow(dead_code)]
+
+pub struct Buf {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Buf {
+    /// `len` carries an invariant since it's written by this unsafe function.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    /// A call that may panic after `len` is extended.
+    pub fn extend(&mut self, values: &[u8]) {
+        let old = self.len;
+        unsafe { self.set_len(old + values.len()) };
+        for (i, v) in values.iter().enumerate() {
+            unsafe { self.ptr.add(old + i).write(*v) };
+        }
+    }
+
+    /// Dropping the vector runs its `Drop` impl after `len` is changed.
+    pub fn replace(&mut self, values: Vec<u8>) {
+        unsafe { self.set_len(values.len()) };
+    }
+}
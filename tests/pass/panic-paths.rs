@@ -0,0 +1,55 @@
+//! Panics after breaking an invariant and before restoring it.
+#![allow(dead_code)]
+
+pub struct Buf {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+}
+
+impl Buf {
+    /// `len` carries an invariant since it's written by this unsafe function.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    /// A bounds check may panic after `len` is extended.
+    pub fn extend(&mut self, values: &[u8]) {
+        let old = self.len;
+        unsafe { self.set_len(old + values.len()) };
+        for i in 0..values.len() {
+            unsafe { self.ptr.add(old + i).write(values[i]) };
+        }
+    }
+
+    /// The write is the last thing before returning.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.len = len;
+        }
+    }
+
+    /// `cap` is not accessed by unsafe functions.
+    pub fn reserve(&mut self, additional: usize) {
+        self.cap = additional;
+        assert!(self.cap >= self.len);
+    }
+
+    /// The first write is overwritten on both branches before the bounds check, so only the
+    /// later writes are followed by the panic.
+    pub fn restore(&mut self, values: &[u8], flag: bool) {
+        let old = self.len;
+        self.len = 0;
+        if flag {
+            self.len = old;
+        } else {
+            self.len = 1;
+        }
+        let _ = values[0];
+    }
+
+    /// Dropping the vector runs its `Drop` impl after `len` is changed.
+    pub fn replace(&mut self, values: Vec<u8>) {
+        unsafe { self.set_len(values.len()) };
+    }
+}
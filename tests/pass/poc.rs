@@ -19,6 +19,16 @@ impl SecretRegion {
         self.len = l;
     }
 
+    /// Calls `set_len` without delegating its tag.
+    pub unsafe fn truncate(&mut self) {
+        self.set_len(0);
+    }
+
+    /// A safe method writing `len` that unsafe methods rely on.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
     #[rapx::requires(ValidPtr(ptr), ValidPtr(self.buffer, offset))]
     pub unsafe fn xor_secret_region(&self, ptr: *mut u32, offset: isize) -> u32 {
         let mut src_value = ptr.read();
@@ -0,0 +1,37 @@
+//! Parameters and return types with raw pointers, references, and adts.
+#![allow(dead_code)]
+#![feature(register_tool)]
+#![register_tool(rapx)]
+
+pub struct Node {
+    next: *mut Node,
+}
+
+/// Patterns and `_` have no parameter names.
+pub unsafe fn link(node: *mut Node, (a, _b): (u8, u8), _: &Node) -> Option<Box<Node>> {
+    let _ = a;
+    unsafe { (*node).next = std::ptr::null_mut() };
+    None
+}
+
+impl Node {
+    pub fn next(&mut self) -> *const Node {
+        self.next
+    }
+
+    /// Tag arguments are resolved to parameters they start with.
+    #[rapx::requires(ValidPtr(self.next), ValidPtr(*node, 1), Align(std::ptr::null::<Node>()))]
+    pub unsafe fn append(&mut self, node: *mut Node) {
+        self.next = node;
+    }
+}
+
+pub trait Visit {
+    fn visit(&self, node: &mut Node);
+}
+
+impl Visit for () {
+    fn visit(&self, node: &mut Node) {
+        node.next = std::ptr::null_mut();
+    }
+}
@@ -0,0 +1,53 @@
+use std::cell::UnsafeCell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static mut COUNTER: usize = 0;
+static ATOMIC: AtomicUsize = AtomicUsize::new(0);
+static LOCKED: Mutex<usize> = Mutex::new(0);
+static CELL: SyncCell = SyncCell(UnsafeCell::new(0));
+const LIMIT: usize = 8;
+
+pub struct SyncCell(UnsafeCell<usize>);
+
+unsafe impl Sync for SyncCell {}
+
+pub fn write_counter(n: usize) {
+    unsafe { COUNTER = n };
+}
+
+pub fn read_counter() -> usize {
+    unsafe { COUNTER }
+}
+
+pub fn bump() -> usize {
+    ATOMIC.fetch_add(1, Ordering::SeqCst)
+}
+
+pub fn lock() -> usize {
+    *LOCKED.lock().unwrap()
+}
+
+pub fn write_cell(n: usize) {
+    unsafe { *CELL.0.get() = n };
+}
+
+pub fn limit() -> usize {
+    LIMIT
+}
+
+pub trait Bounded {
+    const MAX: usize;
+}
+
+impl Bounded for SyncCell {
+    const MAX: usize = 4;
+}
+
+impl SyncCell {
+    pub const MIN: usize = 1;
+
+    pub fn range() -> usize {
+        Self::MAX - Self::MIN
+    }
+}
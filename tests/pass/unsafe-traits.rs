@@ -0,0 +1,53 @@
+use std::ptr::NonNull;
+
+/// A type that can be zero-initialized.
+///
+/// # Safety
+///
+/// All-zero bit pattern must be a valid value of the type.
+pub unsafe trait Zeroable {}
+
+/// A type whose values can be sent as raw bytes.
+///
+/// # Safety
+///
+/// The type must have no padding bytes, like
+///
+/// ```
+/// # use unsafe_traits::Pod;
+/// # struct Pair(u32, u32);
+/// unsafe impl Pod for Pair {}
+/// ```
+///
+/// # Examples
+///
+/// `u32` is `Pod`.
+pub unsafe trait Pod {}
+
+pub struct Buf {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+unsafe impl Send for Buf {}
+unsafe impl Sync for Buf {}
+
+unsafe impl Zeroable for u32 {}
+unsafe impl Pod for u32 {}
+
+pub enum Slot {
+    Empty,
+    Full(*mut u8),
+}
+
+unsafe impl Zeroable for Slot {}
+
+impl Buf {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+}
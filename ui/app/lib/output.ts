@@ -1,35 +1,133 @@
+import { ref } from "vue";
 import { BASE_URL } from "./topbar";
+import type { Access } from "./output/adt";
+import type { TagUsage } from "./output/tag";
 
-export type Caller = {
+// Function attributes in `caller.json` and `doc.json`.
+export type FnMeta = {
+  // `pub`, `pub(crate)`, `pub(in path)`, or `private`.
+  visibility: string,
+  const_fn: boolean,
+  async_fn: boolean,
+  abi: string,
+  inline?: "hint" | "always" | "never" | "force",
+  target_features?: string[],
+  no_mangle: boolean,
+  export_name?: string,
+  track_caller: boolean,
+  // The trait if the function is a method in a trait impl.
+  trait_impl?: string,
+}
+
+// Adts in a signature type, linked to `adt/<key>.json`.
+// `key` is absent if the adt has no `adt/<key>.json`.
+export type AdtLink = { name: string, stable_id: string, key?: string }
+export type SigTyKind = "RawPtr" | "RawPtrMut" | "Ref" | "RefMut" | "Adt" | "Other"
+export type SigTy = { ty: string, kind: SigTyKind, adts?: AdtLink[] }
+// The name is `_` for patterns and unnamed parameters.
+export type Param = SigTy & { name: string }
+// `param` is the index of the parameter the tag argument refers to.
+export type TagArg = { tag: string, arg: string, param?: number }
+export type Signature = { params: Param[], ret: SigTy, tag_args?: TagArg[] }
+
+export type Caller = FnMeta & {
   name: string,
   span: string,
+  stable_id: string,
   safe: boolean,
+  sig: Signature,
+  // Names of safety tags on the function.
+  tags?: string[],
+  leaf?: Leaf,
   callees: Callees,
   adts: AdtFieldFnKind,
+  // The key is the stable id of the static or const.
+  statics: { [key: string]: { name: string, kind: FieldAccessKind } },
+  // Paths where a panic may occur after a possibly broken invariant.
+  panic_paths?: PanicPath[],
   path: { type: PathType, path: string },
 }
 
+export type PanicPath = { change: PathPoint, panic: PathPoint }
+export type PathPointKind = "FieldWrite" | "UnsafeCall" | "Call" | "Assert" | "Drop"
+// `target` is a field like `Adt.field`, a callee, or an assertion message.
+export type PathPoint = { kind: PathPointKind, target: string, span: string, bb: number }
+
 export enum PathType {
   Local = "Local",
   External = "External",
 }
 
+// The key is the stable id of the callee.
 export type Callees = { [key: string]: CalleeInfo };
 
 export type CalleeInfo = {
+  name: string,
+  // Instances of explicit uses, which is empty if the callee is only invoked implicitly.
   instance_name: string[],
   safe: boolean,
+  leaf?: Leaf,
+  // Set if the callee is invoked implicitly, e.g. drop glue.
+  implicit?: Implicit,
+  implicit_instance_name?: string[],
   doc: string,
-  // The key is adt name.
   adt: AdtFieldFnKind,
+  calls?: CallSite[],
+}
+
+export type CallSite = {
+  span: string,
+  bb: number,
+  in_unsafe: boolean,
+  // Sources of each argument passed to the callee.
+  args: ArgSource[][],
+  // Branches and assertions dominating a call to an unsafe callee.
+  guards?: Guard[],
+}
+
+export type Guard = {
+  kind: "SwitchInt" | "Assert",
+  span: string,
+  bb: number,
+  src: string,
+  // The branch taken to reach the call, or the expected value of an assertion.
+  taken: string,
+  msg?: string,
+  sources: ArgSource[],
+}
+
+// `index` of an argument starts from 0, and `place` is like `(*self).len`.
+export type ArgSource =
+  | { kind: "Arg", index: number, place: string }
+  | { kind: "Ret", callee: string }
+  | { kind: "Static", name: string }
+  | { kind: "Const" }
+
+export enum Implicit {
+  Drop = "drop",
+}
+
+// A function without body: FFI or intrinsic.
+export type Leaf = {
+  category: LeafCategory,
+  abi: string,
+  link_name: string,
+}
+
+export enum LeafCategory {
+  Ffi = "ffi",
+  Intrinsic = "intrinsic",
+  Llvm = "llvm",
 }
 
 // The key is adt name.
+// The key is the stable id of the adt.
 export type AdtFieldFnKind = { [key: string]: AdtInfo }
 
 export type AdtInfo = {
+  name: string,
   kind: AdtFnKind,
-  field: { [key: string]: FieldAccessKind }
+  field: { [key: string]: FieldAccessKind },
 }
 
 export enum AdtFnKind {
@@ -48,15 +146,75 @@ export enum FieldAccessKind {
   Other = "Other",
 }
 
-export type Src = { name: string, span: string, src: string, }
-export type Doc = { name: string, span: string, doc: string, }
+// A static or const item.
+export type Static = {
+  name: string,
+  span: string,
+  stable_id: string,
+  kind: StaticKind,
+  // Direct callees in the initializer.
+  callees: Callees,
+  access: Access,
+  path: { type: PathType, path: string },
+}
+
+export enum StaticKind {
+  Static = "Static",
+  StaticMut = "StaticMut",
+  // An immutable static with interior mutability, like `AtomicUsize`.
+  StaticInteriorMut = "StaticInteriorMut",
+  Const = "Const",
+}
+
+// Data in traits.json: unsafe traits and local unsafe impls.
+export type Traits = { traits: UnsafeTrait[] }
+
+export type UnsafeTrait = {
+  name: string,
+  span: string,
+  stable_id: string,
+  // The `# Safety` section in the doc.
+  safety_doc: string,
+  tags: TagUsage[],
+  path: { type: PathType, path: string },
+  impls: UnsafeImpl[],
+}
+
+export type UnsafeImpl = {
+  name: string,
+  span: string,
+  stable_id: string,
+  self_ty: string,
+  // Set if the self type is an adt.
+  adt?: string,
+  // Field name to field type.
+  fields: { [key: string]: string },
+  path: { type: PathType, path: string },
+}
+
+export type Src = { name: string, span: string, stable_id: string, src: string, }
+export type Doc = { name: string, span: string, stable_id: string, doc: string, } & Partial<FnMeta>
 export type Mir = { name: string, span: string, mir: string, }
 
 export const EMPTY_SRC: Src = { name: "", span: "", src: "" }
 export const EMPTY_DOC: Doc = { name: "", span: "", doc: "" }
 export const EMPTY_MIR: Mir = { name: "", span: "", mir: "" }
 
-export function functionURL(name: string, info: string): string | undefined {
+// Names to storage keys from `keys.json` of each crate.
+export type StorageKeys = { [crate: string]: { [name: string]: string[] } }
+export const STORAGE_KEYS = ref<StorageKeys>({})
+
+// The directory or file name of the item. Data without `keys.json` is stored by names.
+// Items may share a name, so the key ending with the local part of the stable id is picked
+// if given, otherwise the first key.
+export function storageKey(crate: string, name: string, stableId?: string): string {
+  const keys = STORAGE_KEYS.value[crate]?.[name]
+  if (!keys?.length) return name
+  const local = stableId?.slice(16)
+  return (local && keys.find(key => key.endsWith(`~${local}`))) || keys[0]!
+}
+
+export function functionURL(name: string, info: string, stableId?: string): string | undefined {
   if (typeof name !== "string") return undefined;
   // name must be `{crate_name}::{func_name}`
   const pat = /(\w+)::(.*)/;
@@ -64,13 +222,13 @@ export function functionURL(name: string, info: string): string | undefined {
   if (!matched) return undefined;
   const crate = matched[1];
   // const fn = matched[2];
-  return (crate) ? `${BASE_URL}/${crate}/${name}/${info}.json` : undefined;
+  return (crate) ? `${BASE_URL}/${crate}/${storageKey(crate, name, stableId)}/${info}.json` : undefined;
 }
 
-export const callerURL = (name: string) => functionURL(name, "caller")
-export const srcURL = (name: string) => functionURL(name, "src")
-export const docURL = (name: string) => functionURL(name, "doc")
-export const mirURL = (name: string) => functionURL(name, "mir")
+export const callerURL = (name: string, stableId?: string) => functionURL(name, "caller", stableId)
+export const srcURL = (name: string, stableId?: string) => functionURL(name, "src", stableId)
+export const docURL = (name: string, stableId?: string) => functionURL(name, "doc", stableId)
+export const mirURL = (name: string, stableId?: string) => functionURL(name, "mir", stableId)
 
 export const EMPTY_CALLER: Caller = {
   name: "", span: "", safe: true, callees: {}, adts: {}, path: { type: PathType.Local, path: "" },
@@ -9,7 +9,8 @@ export type AdtClicked = {
 
 export type DataAdt = {
   name: string,
-  constructors: string[],
+  instances: string[],
+  constructors: FnRef[],
   access_self_as_arg: Access,
   access_self_as_locals: Access,
   access_field: Access[],
@@ -18,17 +19,22 @@ export type DataAdt = {
   kind: string,
   doc_adt: string,
   variant_fields: { [key: string]: VariantField },
+  stable_id: string,
 }
 
 export type Access = {
-  read: string[],
-  write: string[],
-  other: string[],
+  read: FnRef[],
+  write: FnRef[],
+  other: FnRef[],
 }
 
+/** A function by name, and the stable id to tell apart functions sharing the name. */
+export type FnRef = { name: string, stable_id: string }
+
 export type VariantField = {
   name: string,
   doc: string,
+  stable_id: string,
 }
 
 export function adtDoc(adt: DataAdt) {
@@ -11,7 +11,9 @@ export const enum Panel {
 export const PANELS: Panel[] = [Panel.Adt, Panel.Tag, Panel.Doc, Panel.Src, Panel.Mir];
 
 export type PanelContent = {
-  nodeItem: string
+  nodeItem: string,
+  // Picks the item among items sharing the name.
+  stableId?: string,
 }
 
 export function toPanelStr(p: Panel): string {
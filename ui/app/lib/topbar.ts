@@ -1,3 +1,5 @@
+import { storageKey } from "./output";
+
 
 /** The way to view UPG. */
 export enum ViewType {
@@ -35,8 +37,19 @@ export enum DefPathKind {
   TraitDecl = "TraitDecl",
   Ty = "Ty",
   ImplTrait = "ImplTrait",
+  Static = "Static",
+  Const = "Const",
+  Ref = "Ref",
+  Ptr = "Ptr",
+  Slice = "Slice",
+  Tuple = "Tuple",
+  Array = "Array",
+  Param = "Param",
 }
 
+/** Self types of impls that are not adts. */
+const SELF_TYS: string[] = [DefPathKind.Ty, DefPathKind.Ref, DefPathKind.Ptr, DefPathKind.Slice, DefPathKind.Tuple, DefPathKind.Array, DefPathKind.Param]
+
 /** Returns an icon string for a DefPathKind.
  * The icon must be maintained in nuxt config.*/
 export function icon(kind: DefPathKind | string): string {
@@ -50,7 +63,9 @@ export function icon(kind: DefPathKind | string): string {
     case DefPathKind.TraitDecl: return "tabler:letter-t";
     case DefPathKind.Ty: return "tabler:letter-t-small";
     case DefPathKind.ImplTrait: return "tabler:letter-t";
-    default: return "tabler:alert-circle";
+    case DefPathKind.Static: return "tabler:letter-v";
+    case DefPathKind.Const: return "tabler:letter-c";
+    default: return SELF_TYS.includes(kind) ? "tabler:letter-t-small" : "tabler:alert-circle";
   }
 }
 
@@ -65,7 +80,9 @@ export function colorClass(kind: DefPathKind | string): string {
     case DefPathKind.TraitDecl: return "def-trait";
     case DefPathKind.Ty: return "def-ty";
     case DefPathKind.ImplTrait: return "def-trait";
-    default: return "";
+    case DefPathKind.Static: return "def-static";
+    case DefPathKind.Const: return "def-static";
+    default: return SELF_TYS.includes(kind) ? "def-ty" : "";
   }
 }
 
@@ -80,6 +97,8 @@ export function urlKind(kind: DefPathKind): string {
     case DefPathKind.TraitDecl: return "adt";
     case DefPathKind.Ty: return "unknown";
     case DefPathKind.ImplTrait: return "adt";
+    case DefPathKind.Static: return "static";
+    case DefPathKind.Const: return "static";
     default: return "unknown";
   }
 }
@@ -91,16 +110,29 @@ export type DefPath = {
 
 export type Navi = {
   tree: NaviTree,
-  name_to_id: { [key: string]: number }
+  // Stable ids of items to their names and node ids.
+  items: { [stable_id: string]: { name: string, id: number } }
+}
+
+/** Unsafety counts of a subtree. Absent if all counts are zero. */
+export type NaviStats = {
+  fns: number,
+  unsafe_fns: number,
+  safe_fns_with_unsafe_callees: number,
+  unsafe_blocks: number,
+  tagged_unsafe_fns: number,
+  untagged_unsafe_fns: number,
+  adts_with_raw_ptr_fields: number,
 }
 
 export type NaviTree = {
-  node: DefPath & { id: number },
+  node: DefPath & { id: number, stable_id?: string },
+  stats?: NaviStats,
   sub: NaviTree[],
 }
 
 export const NAVI_TREE: NaviTree = { node: { kind: DefPathKind.Mod, name: "", id: 0 }, sub: [] }
-export const NAVI: Navi = { tree: NAVI_TREE, name_to_id: {} }
+export const NAVI: Navi = { tree: NAVI_TREE, items: {} }
 
 // flow options
 
@@ -174,7 +206,11 @@ export function adtURL(adtName: string) {
   const crate = adtName.match(/^[^:]+/)?.[0]
   if (!crate) return ""
 
-  return `${BASE_URL}/${crate}/adt/${adtName}.json`;
+  return `${BASE_URL}/${crate}/adt/${storageKey(crate, adtName)}.json`;
+}
+
+export function keysURL(crate: Crate) {
+  return `${BASE_URL}/${crate}/keys.json`;
 }
 
 export function naviTreeURL(crate: Crate) {
@@ -197,16 +233,17 @@ export type BarPlotData = {
 
 export type Search = { withTags: boolean, unsafeOnly: boolean, text: string, page: number, itemsPerPage: number }
 
-export type SearchFnItem = { name: string, tags: string[] }
+export type SearchFnItem = { name: string, stable_id?: string, tags: string[] }
 
 export function unsafeFnsURL(crate: Crate) {
   return `${BASE_URL}/${crate}/navi/unsafe_fns.json`
 }
 
+/** Keyed by stable ids of functions. */
 export type UnsafeFns = {
-  [key: string]: Unsafe
+  [stable_id: string]: { name: string, kind: Unsafe }
 }
 
 export enum Unsafe {
-  Caller = "Caller", Callee = " Callee", Both = "Both",
+  Caller = "Caller", Callee = " Callee", Both = "Both", Leaf = "Leaf",
 }
@@ -101,11 +101,12 @@ export class PlotConfig {
   }
 
   calleeChildren(callees: Callees, withAdt: string): ElkNode[] {
-    return Object.entries(callees).map(([name, info]) => {
+    return Object.entries(callees).map(([stable_id, info]) => {
+      const name = info.name;
       const labelDim = this.size(name);
-      const id = idCalleeWithAdt(name, withAdt);
+      const id = idCalleeWithAdt(stable_id, withAdt);
       this.id_to_item[id] = {
-        name: name,
+        name, stable_id,
         kind: info.safe ?
           (withAdt ? NodeKind.SafeFnWithAdt : NodeKind.SafeFn) :
           (withAdt ? NodeKind.UnsafeFnWithAdt : NodeKind.UnsafeFn)
@@ -124,12 +125,13 @@ export class PlotConfig {
   calleeUnsafe(callees: Callees): ElkNode[] {
     const nodes: ElkNode[] = []
 
-    for (const [name, info] of Object.entries(callees)) {
+    for (const [stable_id, info] of Object.entries(callees)) {
       // if (info.safe) continue;
 
+      const name = info.name;
       const labelDim = this.size(name);
-      const id = idCalleeNonGeneric(name);
-      this.id_to_item[id] = { name, kind: info.safe ? NodeKind.SafeFn : NodeKind.UnsafeFn };
+      const id = idCalleeNonGeneric(stable_id);
+      this.id_to_item[id] = { name, stable_id, kind: info.safe ? NodeKind.SafeFn : NodeKind.UnsafeFn };
       const tags = this.tagChildren(name)
       nodes.push({
         id, layoutOptions: FnLayoutOptions,
@@ -264,8 +266,8 @@ function nodeKindType(kind: NodeKind) {
 }
 
 /** id_to_item: Node id to item (e.g. fn, callee, adt) name.
-    Rust forbids identical path to different items, so the name is trustworthy. */
-export type IdToItem = { [key: string]: { name: string, kind: NodeKind } };
+    Items like fns in different impls may share a name, so callees also carry the stable id. */
+export type IdToItem = { [key: string]: { name: string, kind: NodeKind, stable_id?: string } };
 
 export class Plot {
   nodes: Node[];
@@ -315,7 +317,13 @@ export class Plot {
 
     const callees = config.calleeChildren(fn.callees, "");
 
-    const edges: Edge[] = callees.map(c => ({ id: idEdge(root.id, c.id), source: root.id, target: c.id, type: config.flowOpts.edge as string }));
+    const edges: Edge[] = callees.map(c => {
+      const edge: Edge = { id: idEdge(root.id, c.id), source: root.id, target: c.id, type: config.flowOpts.edge as string };
+      // Callees only invoked implicitly like drop glue are drawn as dashed edges.
+      const callee = fn.callees[config.id_to_item[c.id]?.stable_id ?? ""];
+      const implicit = callee?.instance_name.length ? undefined : callee?.implicit;
+      return implicit ? { ...edge, label: implicit, style: { strokeDasharray: "5 5" } } : edge;
+    });
 
     const graph: ElkNode = {
       id: "__root",
@@ -355,37 +363,41 @@ export class Plot {
     const id_to_item = this.config.id_to_item;
     const edgeType = config.edgeType();
 
-    // The key is adt name, the value is callee name.
-    const adt_to_fnKind: { [keys: string]: { name: string, kind: AdtFnKind, info: CalleeInfo }[] } = {};
-    // The outer key is adt name, inner key is field name, and the value is callee name.
-    const field_access: { [keys: string]: { [keys: string]: { name: string, access: FieldAccessKind, info: CalleeInfo }[] } } = {};
-    for (const [callee_name, info] of Object.entries(fn.callees)) {
-      // Callee name has been unqiue, so we push it to the adts.
+    // The key is adt stable id, the value is callee stable id.
+    const adt_to_fnKind: { [keys: string]: { id: string, kind: AdtFnKind, info: CalleeInfo }[] } = {};
+    // The outer key is adt stable id, inner key is field name, and the value is callee stable id.
+    const field_access: { [keys: string]: { [keys: string]: { id: string, access: FieldAccessKind, info: CalleeInfo }[] } } = {};
+    // Adt stable id to adt name.
+    const adt_names: { [keys: string]: string } = {};
+    for (const [callee_id, info] of Object.entries(fn.callees)) {
+      // Callee stable id is unqiue, so we push it to the adts.
       for (const [adt, adt_info] of Object.entries(info.adt)) {
+        adt_names[adt] = adt_info.name;
         adt_to_fnKind[adt] ??= [];
-        adt_to_fnKind[adt].push({ name: callee_name, kind: adt_info.kind, info });
+        adt_to_fnKind[adt].push({ id: callee_id, kind: adt_info.kind, info });
 
         for (const [field, access] of Object.entries(adt_info.field)) {
           field_access[adt] ??= {};
           field_access[adt][field] ??= [];
-          field_access[adt][field].push({ name: callee_name, access, info });
+          field_access[adt][field].push({ id: callee_id, access, info });
         }
       }
     }
 
     const edgesBetweenFieldsAndCallee: Edge[] = []
-    const callees_with_adt = new Set<string>(); // Callee names.
-    const adtNodes: ElkNode[] = Object.entries(adt_to_fnKind).map(([adtName, callees]) => {
+    const callees_with_adt = new Set<string>(); // Callee stable ids.
+    const adtNodes: ElkNode[] = Object.entries(adt_to_fnKind).map(([adtId, callees]) => {
+      const adtName = adt_names[adtId]!;
       const edgesInAdt: Edge[] = []
       const labelDim = config.size(adtName);
       const adt_id = idAdt(adtName);
       id_to_item[adt_id] = { name: adtName, kind: NodeKind.Adt };
 
       const kinds: { [key: string]: Callees } = {};
-      for (const { kind, name, info } of callees) {
+      for (const { kind, id, info } of callees) {
         kinds[kind] ??= {};
-        kinds[kind][name] = info;
-        callees_with_adt.add(name);
+        kinds[kind][id] = info;
+        callees_with_adt.add(id);
       }
       const kindNodes: ElkNode[] = Object.entries(kinds).map(([kind, callees]) => {
         const id_adt_fnKind = idAdtFnKind(adt_id, kind);
@@ -399,7 +411,7 @@ export class Plot {
       });
 
       const fieldsChildren: ElkNode[] = [];
-      const adt_fields = field_access[adtName];
+      const adt_fields = field_access[adtId];
       if (adt_fields) {
         for (const [field, callees] of Object.entries(adt_fields)) {
           const id_field = idField(adtName, field)
@@ -412,8 +424,8 @@ export class Plot {
           })
 
           for (const callee of callees) {
-            const fnKind = callee.info.adt[adtName]!.kind
-            const source = idCalleeKindAdt(callee.name, fnKind, adtName)
+            const fnKind = callee.info.adt[adtId]!.kind
+            const source = idCalleeKindAdt(callee.id, fnKind, adtName)
             edgesInAdt.push({ id: idEdge(source, id_field), source, target: id_field, type: "step", label: callee.access })
           }
         }
@@ -446,8 +458,8 @@ export class Plot {
 
     // Add callees that have no adts to the graph.
     const callees_no_adt: Callees = {};
-    for (const [name, info] of Object.entries(fn.callees)) {
-      if (!callees_with_adt.has(name)) callees_no_adt[name] = info;
+    for (const [id, info] of Object.entries(fn.callees)) {
+      if (!callees_with_adt.has(id)) callees_no_adt[id] = info;
     }
     const calleesNoAdt = config.calleeChildren(callees_no_adt, "");
 
@@ -546,7 +558,7 @@ export class Plot {
 
     // Determine the caller's method adt
     let adt: { name: string, kind: AdtFnKind, field: { name: string, access: FieldAccessKind }[] } | null = null
-    for (const [name, info] of Object.entries(fn.adts)) {
+    for (const info of Object.values(fn.adts)) {
       const kind = info.kind
       switch (kind) {
         case AdtFnKind.MethodImmutableRefReceiver:
@@ -556,7 +568,7 @@ export class Plot {
           const field = Object.entries(info.field)
             .map(([name, access]) => ({ name, access }))
             .filter(f => f.access !== FieldAccessKind.Other)
-          adt = { name, kind, field }
+          adt = { name: info.name, kind, field }
         }
       }
     }
@@ -17,6 +17,8 @@ export default defineNuxtConfig({
         'tabler:letter-u', // union
         'tabler:letter-t', // trait
         'tabler:letter-t-small', // SelfTy
+        'tabler:letter-v', // static
+        'tabler:letter-c', // const
         'tabler:square-letter-f',// function
         'tabler:letter-m-small', // method
         'tabler:square-rounded-letter-p-filled', // safety property